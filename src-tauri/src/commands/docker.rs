@@ -0,0 +1,47 @@
+use tauri::State;
+
+use crate::docker::client::DockerStatus;
+use crate::docker::engine::{self, Endpoint, ENGINE_HOST_SETTING};
+use crate::docker::DockerClient;
+use crate::error::LdbError;
+use crate::state::StateManager;
+
+/// Health-check the Docker daemon and report whether it is reachable
+///
+/// Never fails: an unreachable daemon is reported through `available` and
+/// `error`. Changes are also pushed as `docker-status-changed` events.
+#[tauri::command]
+pub async fn get_docker_status(client: State<'_, DockerClient>) -> Result<DockerStatus, LdbError> {
+    Ok(client.status().await)
+}
+
+/// List the Docker and Podman engines found on this machine
+#[tauri::command]
+pub fn list_container_engines() -> Vec<Endpoint> {
+    engine::discover()
+}
+
+/// Get the engine address picked in settings; `None` means auto-discovery
+#[tauri::command]
+pub fn get_container_engine() -> Result<Option<String>, LdbError> {
+    StateManager::new()?.get_setting(ENGINE_HOST_SETTING)
+}
+
+/// Pick the engine to connect to, or `None` to auto-discover, and reconnect
+#[tauri::command]
+pub async fn set_container_engine(
+    client: State<'_, DockerClient>,
+    host: Option<String>,
+) -> Result<DockerStatus, LdbError> {
+    let host = host.filter(|h| !h.trim().is_empty());
+
+    // Reject addresses we cannot connect to at all before saving them
+    if let Some(host) = &host {
+        engine::connect(host)?;
+    }
+
+    StateManager::new()?.set_setting(ENGINE_HOST_SETTING, host.as_deref())?;
+
+    client.reset().await;
+    Ok(client.status().await)
+}
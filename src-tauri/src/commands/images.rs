@@ -1,6 +1,8 @@
 use crate::docker::hub::DockerHubClient;
 use crate::docker::client::DockerClient;
-use tauri::AppHandle;
+use tauri::{AppHandle, State};
+
+use crate::error::LdbError;
 
 // Reduce pages to improve performance (25-50 tags instead of 100)
 const MAX_PAGES: usize = 2;
@@ -14,6 +16,12 @@ pub struct ImageTag {
     pub is_recommended: Option<bool>,
 }
 
+/// Architecture prefixes of single-platform tags
+const PLATFORM_PREFIXES: &[&str] = &["x86_64_", "arm64_", "aarch64_", "arm32v7_"];
+
+/// Ubuntu releases used as base image suffixes
+const UBUNTU_CODENAMES: &[&str] = &["focal", "jammy", "noble"];
+
 /// Categorize and prioritize tags for better UX
 fn categorize_tag(name: &str) -> (String, bool) {
     let lower = name.to_lowercase();
@@ -24,10 +32,16 @@ fn categorize_tag(name: &str) -> (String, bool) {
     // Categorize the tag
     let category = if is_recommended {
         "recommended".to_string()
+    } else if PLATFORM_PREFIXES.iter().any(|p| lower.starts_with(p) || lower.contains(&format!("_{}", p))) {
+        // KeyDB publishes per-architecture tags, e.g. "x86_64_v6.3.4"
+        "platform".to_string()
     } else if lower.contains("alpine") {
         "alpine".to_string()
     } else if lower.contains("slim") {
         "slim".to_string()
+    } else if UBUNTU_CODENAMES.iter().any(|c| lower.ends_with(c)) {
+        // MariaDB publishes variants per Ubuntu base, e.g. "11.4-noble"
+        "ubuntu".to_string()
     } else if lower.chars().all(|c| c.is_ascii_digit() || c == '.') {
         // Pure version numbers like "16", "15.4"
         "version".to_string()
@@ -48,12 +62,18 @@ fn is_recommended_tag(name: &str) -> bool {
         "16", "15", "14", "13", "12",
         "8", "7", "6",
         "bullseye",
+        // MariaDB long-term support releases
+        "lts", "11.4", "10.11", "10.6",
+        // Valkey release lines (KeyDB only publishes multi-arch "latest")
+        "8.1", "8.1-alpine", "8.0", "8.0-alpine", "7.2", "7.2-alpine",
+        // SQL Server release lines
+        "2022-latest", "2019-latest",
     ];
     
     recommended_patterns.iter().any(|p| name == *p)
 }
 
-/// Sort tags with recommended first, then alpine, then version-based
+/// Sort tags with recommended first, then alpine, then by semantic version
 fn sort_tags(tags: &mut [ImageTag]) {
     // Helper to get category priority (lower = shown first)
     fn category_priority(cat: &Option<String>) -> u8 {
@@ -62,20 +82,17 @@ fn sort_tags(tags: &mut [ImageTag]) {
             Some("alpine") => 1,
             Some("slim") => 2,
             Some("version") => 3,
-            _ => 4,
+            Some("ubuntu") => 4,
+            Some("platform") => 6,
+            _ => 5,
         }
     }
     
-    // Helper to extract numeric prefix for version sorting
-    fn version_key(name: &str) -> (bool, i32, &str) {
-        // Returns (has_numeric_prefix, numeric_value, full_name)
-        // This allows consistent sorting: numeric prefixes first (descending), then non-numeric (ascending)
+    // Helper to extract the version prefix for semver sorting, e.g. "8.15.1-alpine" -> [8, 15, 1]
+    fn version_key(name: &str) -> Option<Vec<u64>> {
         let prefix = name.split('-').next().unwrap_or(name);
-        if let Ok(num) = prefix.parse::<i32>() {
-            (true, num, name)
-        } else {
-            (false, 0, name)
-        }
+        let prefix = prefix.strip_prefix('v').unwrap_or(prefix);
+        prefix.split('.').map(|part| part.parse().ok()).collect()
     }
     
     tags.sort_by(|a, b| {
@@ -89,18 +106,18 @@ fn sort_tags(tags: &mut [ImageTag]) {
                 let a_key = version_key(&a.name);
                 let b_key = version_key(&b.name);
                 
-                // Numeric prefixes come first (sorted descending), then non-numeric (sorted ascending)
-                match (a_key.0, b_key.0) {
-                    (true, true) => {
-                        // Both have numeric prefixes: higher versions first (descending)
-                        b_key.1.cmp(&a_key.1).then_with(|| a_key.2.cmp(b_key.2))
+                // Versions come first (sorted descending, component by component), then the rest (sorted ascending)
+                match (a_key, b_key) {
+                    (Some(a_version), Some(b_version)) => {
+                        // Both are versions: higher first, so 8.15.1 precedes 8.9.0
+                        b_version.cmp(&a_version).then_with(|| a.name.cmp(&b.name))
                     }
-                    (false, false) => {
-                        // Neither has numeric prefix: alphabetical ascending
-                        a_key.2.cmp(b_key.2)
+                    (None, None) => {
+                        // Neither is a version: alphabetical ascending
+                        a.name.cmp(&b.name)
                     }
-                    (true, false) => std::cmp::Ordering::Less,  // Numeric comes first
-                    (false, true) => std::cmp::Ordering::Greater, // Non-numeric comes after
+                    (Some(_), None) => std::cmp::Ordering::Less,  // Versions come first
+                    (None, Some(_)) => std::cmp::Ordering::Greater, // Others come after
                 }
             }
             other => other,
@@ -110,7 +127,7 @@ fn sort_tags(tags: &mut [ImageTag]) {
 
 /// Get available tags for a Docker Hub image
 #[tauri::command]
-pub async fn get_docker_tags(image: String) -> Result<Vec<ImageTag>, String> {
+pub async fn get_docker_tags(image: String) -> Result<Vec<ImageTag>, LdbError> {
     let client = DockerHubClient::new();
 
     // Fetch limited pages to prevent long loading times
@@ -157,14 +174,50 @@ pub fn get_supported_images() -> Vec<(&'static str, &'static str)> {
     vec![
         ("postgres", "library/postgres"),
         ("redis", "library/redis"),
+        ("valkey", "valkey/valkey"),
+        ("keydb", "eqalpha/keydb"),
         ("mysql", "library/mysql"),
+        ("mariadb", "library/mariadb"),
         ("mongo", "library/mongo"),
+        ("clickhouse", "clickhouse/clickhouse-server"),
+        ("elasticsearch", "library/elasticsearch"),
+        ("opensearch", "opensearchproject/opensearch"),
+        ("sqlserver", "mcr.microsoft.com/mssql/server"),
     ]
 }
 
 /// Pull a Docker image with progress events
 #[tauri::command]
-pub async fn pull_docker_image(app: AppHandle, image: String) -> Result<(), String> {
-    let client = DockerClient::new()?;
+pub async fn pull_docker_image(
+    app: AppHandle,
+    client: State<'_, DockerClient>,
+    image: String,
+) -> Result<(), LdbError> {
     client.pull_image(app, &image).await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(name: &str, category: &str) -> ImageTag {
+        ImageTag { name: name.to_string(), category: Some(category.to_string()), is_recommended: None }
+    }
+
+    #[test]
+    fn sort_tags_orders_by_category_then_version() {
+        let mut tags = vec![
+            tag("8.9.0", "version"),
+            tag("latest", "recommended"),
+            tag("8.15.1", "version"),
+            tag("8-alpine", "alpine"),
+            tag("nightly", "version"),
+            tag("8.15.1-rc1", "version"),
+        ];
+
+        sort_tags(&mut tags);
+
+        let names: Vec<&str> = tags.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["latest", "8-alpine", "8.15.1", "8.15.1-rc1", "8.9.0", "nightly"]);
+    }
+}
@@ -1,29 +1,39 @@
 use bollard::container::{Config, CreateContainerOptions, ListContainersOptions, RemoveContainerOptions, StartContainerOptions, StopContainerOptions};
+use bollard::models::{ContainerInspectResponse, HealthConfig, HealthStatusEnum, PortBinding};
 use bollard::Docker;
 use chrono::Utc;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
 use uuid::Uuid;
 
-use crate::commands::ports::{get_next_port_for_type, get_occupied_ports};
-use crate::models::instance::{CreateInstanceRequest, DatabaseType, Instance, InstanceStatus};
+use crate::commands::connections;
+use crate::commands::ports::{allocate_named_ports, conflicting_ports, get_container_port, get_extra_ports};
+use crate::commands::reconcile;
+use crate::commands::volumes;
+use crate::docker::engine::{self, EngineInfo};
+use crate::docker::events;
+use crate::docker::labels;
+use crate::docker::DockerClient;
+use crate::error::LdbError;
+use crate::models::instance::{
+    BindAddress, CreateInstanceRequest, DatabaseType, Instance, InstancePortChangedEvent, InstanceStatus, PRIMARY_PORT,
+};
+use crate::readiness;
+use crate::secrets;
 use crate::state::StateManager;
 
-/// Get the default volume mount path for a database type
-fn get_volume_path(database_type: &DatabaseType) -> &str {
-    match database_type {
-        DatabaseType::PostgreSQL => "/data",
-        DatabaseType::MySQL => "/var/lib/mysql",
-        DatabaseType::MongoDB => "/data",
-        DatabaseType::Redis => "/data",
-    }
-}
+/// JVM heap of search engines; the images otherwise size it from host memory
+const SEARCH_HEAP_OPTS: &str = "-Xms512m -Xmx512m";
 
 /// Get environment variables for a database type
-/// Password is required for PostgreSQL and MySQL, optional for Redis and MongoDB
+/// Password is required for PostgreSQL, MySQL, MariaDB and SQL Server, optional for MongoDB,
+/// ClickHouse, the search engines and the Redis-compatible engines
 fn get_env_vars(database_type: &DatabaseType, password: &str) -> Vec<(String, String)> {
     match database_type {
         DatabaseType::PostgreSQL => vec![("POSTGRES_PASSWORD".to_string(), password.to_string())],
         DatabaseType::MySQL => vec![("MYSQL_ROOT_PASSWORD".to_string(), password.to_string())],
+        DatabaseType::MariaDB => vec![("MARIADB_ROOT_PASSWORD".to_string(), password.to_string())],
         DatabaseType::MongoDB => {
             // MongoDB can work without authentication
             if password.is_empty() {
@@ -35,35 +45,252 @@ fn get_env_vars(database_type: &DatabaseType, password: &str) -> Vec<(String, St
                 ]
             }
         }
-        DatabaseType::Redis => vec![],
+        DatabaseType::Redis | DatabaseType::KeyDB => {
+            // Lets redis-cli (keydb-cli) in the health check authenticate
+            if password.is_empty() {
+                vec![]
+            } else {
+                vec![("REDISCLI_AUTH".to_string(), password.to_string())]
+            }
+        }
+        DatabaseType::ClickHouse => {
+            if password.is_empty() {
+                // Without this the image limits the passwordless default user to localhost
+                vec![("CLICKHOUSE_SKIP_USER_SETUP".to_string(), "1".to_string())]
+            } else {
+                vec![
+                    ("CLICKHOUSE_USER".to_string(), "default".to_string()),
+                    ("CLICKHOUSE_PASSWORD".to_string(), password.to_string()),
+                    ("CLICKHOUSE_DEFAULT_ACCESS_MANAGEMENT".to_string(), "1".to_string()),
+                ]
+            }
+        }
+        DatabaseType::Valkey => {
+            // valkey-cli reads its own variable and falls back to the Redis one
+            if password.is_empty() {
+                vec![]
+            } else {
+                vec![
+                    ("VALKEYCLI_AUTH".to_string(), password.to_string()),
+                    ("REDISCLI_AUTH".to_string(), password.to_string()),
+                ]
+            }
+        }
+        DatabaseType::Elasticsearch => {
+            let mut env = vec![
+                ("discovery.type".to_string(), "single-node".to_string()),
+                ("ES_JAVA_OPTS".to_string(), SEARCH_HEAP_OPTS.to_string()),
+            ];
+            if password.is_empty() {
+                env.push(("xpack.security.enabled".to_string(), "false".to_string()));
+            } else {
+                // Setting security explicitly skips the auto-generated TLS setup,
+                // so clients can use plain HTTP with the elastic user
+                env.extend([
+                    ("ELASTIC_PASSWORD".to_string(), password.to_string()),
+                    ("xpack.security.enabled".to_string(), "true".to_string()),
+                    ("xpack.security.http.ssl.enabled".to_string(), "false".to_string()),
+                ]);
+            }
+            env
+        }
+        DatabaseType::OpenSearch => {
+            let mut env = vec![
+                ("discovery.type".to_string(), "single-node".to_string()),
+                ("OPENSEARCH_JAVA_OPTS".to_string(), SEARCH_HEAP_OPTS.to_string()),
+            ];
+            if password.is_empty() {
+                env.extend([
+                    ("DISABLE_SECURITY_PLUGIN".to_string(), "true".to_string()),
+                    ("DISABLE_INSTALL_DEMO_CONFIG".to_string(), "true".to_string()),
+                ]);
+            } else {
+                // The demo certificates still secure the transport layer; HTTP stays plain
+                env.extend([
+                    ("OPENSEARCH_INITIAL_ADMIN_PASSWORD".to_string(), password.to_string()),
+                    ("plugins.security.ssl.http.enabled".to_string(), "false".to_string()),
+                ]);
+            }
+            env
+        }
+        DatabaseType::SqlServer => vec![
+            ("ACCEPT_EULA".to_string(), "Y".to_string()),
+            ("MSSQL_PID".to_string(), "Developer".to_string()),
+            ("MSSQL_SA_PASSWORD".to_string(), password.to_string()),
+        ],
     }
 }
 
-/// Get the command for a database type (used for Redis which uses CMD instead of ENV)
-/// Only adds --requirepass if a password is provided
-fn get_database_command(database_type: &DatabaseType, password: &str) -> Option<Vec<String>> {
+/// Check a password against the rules of the database, before Docker is involved
+///
+/// SQL Server refuses to start with an SA password that is not at least 8
+/// characters long and drawn from three of: uppercase letters, lowercase
+/// letters, digits and symbols; OpenSearch needs all four. The container
+/// would only fail later, in its logs.
+fn validate_password(database_type: &DatabaseType, password: &str) -> Result<(), LdbError> {
     match database_type {
-        DatabaseType::Redis => {
-            if password.is_empty() {
-                // Redis without authentication
-                Some(vec!["redis-server".to_string()])
-            } else {
-                // Redis with password authentication
-                Some(vec![
-                    "redis-server".to_string(),
-                    "--requirepass".to_string(),
-                    password.to_string(),
-                ])
+        DatabaseType::SqlServer => {
+            let classes = [
+                password.chars().any(|c| c.is_uppercase()),
+                password.chars().any(|c| c.is_lowercase()),
+                password.chars().any(|c| c.is_ascii_digit()),
+                password.chars().any(|c| !c.is_alphanumeric()),
+            ];
+
+            let length = password.chars().count();
+            if !(8..=128).contains(&length) || classes.iter().filter(|c| **c).count() < 3 {
+                return Err(LdbError::invalid_input(
+                    "SQL Server passwords must be 8 to 128 characters long and contain three of: \
+                     uppercase letters, lowercase letters, digits and symbols",
+                ));
             }
+            Ok(())
         }
+        // OpenSearch 2.12+ refuses a weak initial admin password; without one
+        // security is disabled
+        DatabaseType::OpenSearch if !password.is_empty() => {
+            let strong = password.chars().count() >= 8
+                && password.chars().any(|c| c.is_uppercase())
+                && password.chars().any(|c| c.is_lowercase())
+                && password.chars().any(|c| c.is_ascii_digit())
+                && password.chars().any(|c| !c.is_alphanumeric());
+
+            if !strong {
+                return Err(LdbError::invalid_input(
+                    "OpenSearch passwords must be at least 8 characters long and contain \
+                     uppercase letters, lowercase letters, digits and symbols",
+                ));
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Get the user a database's container runs as, where the image default does not work
+///
+/// SQL Server 2019+ runs as UID 10001, which cannot write a bind-mounted
+/// data directory owned by the host user.
+fn get_container_user(database_type: &DatabaseType) -> Option<&'static str> {
+    match database_type {
+        DatabaseType::SqlServer => Some("root"),
         _ => None,
     }
 }
 
+/// Read the root password back from a container's environment
+///
+/// The inverse of `get_env_vars`; Redis and its forks keep their password in
+/// the command line, so `REDISCLI_AUTH` is the only place to find it. MariaDB images
+/// still accept the MySQL variable names.
+pub fn password_from_env(database_type: &DatabaseType, env: &[String]) -> String {
+    let keys: &[&str] = match database_type {
+        DatabaseType::PostgreSQL => &["POSTGRES_PASSWORD"],
+        DatabaseType::MySQL => &["MYSQL_ROOT_PASSWORD"],
+        DatabaseType::MariaDB => &["MARIADB_ROOT_PASSWORD", "MYSQL_ROOT_PASSWORD"],
+        DatabaseType::MongoDB => &["MONGO_INITDB_ROOT_PASSWORD"],
+        DatabaseType::ClickHouse => &["CLICKHOUSE_PASSWORD"],
+        DatabaseType::Elasticsearch => &["ELASTIC_PASSWORD"],
+        DatabaseType::OpenSearch => &["OPENSEARCH_INITIAL_ADMIN_PASSWORD"],
+        // SA_PASSWORD is the name older images used
+        DatabaseType::SqlServer => &["MSSQL_SA_PASSWORD", "SA_PASSWORD"],
+        DatabaseType::Redis | DatabaseType::Valkey | DatabaseType::KeyDB => &["REDISCLI_AUTH"],
+    };
+
+    keys.iter()
+        .find_map(|key| env.iter().find_map(|e| e.strip_prefix(key)?.strip_prefix('=')))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Get the command for a database type (used for the Redis-compatible engines,
+/// which take their password on the command line instead of from ENV)
+/// Only adds --requirepass if a password is provided
+fn get_database_command(database_type: &DatabaseType, password: &str) -> Option<Vec<String>> {
+    let server: &[&str] = match database_type {
+        DatabaseType::Redis => &["redis-server"],
+        DatabaseType::Valkey => &["valkey-server"],
+        // The image's stock config listens on every interface and sets the data dir
+        DatabaseType::KeyDB => &["keydb-server", "/etc/keydb/keydb.conf"],
+        _ => return None,
+    };
+
+    let mut command: Vec<String> = server.iter().map(|arg| arg.to_string()).collect();
+    if !password.is_empty() {
+        command.extend(["--requirepass".to_string(), password.to_string()]);
+    }
+
+    Some(command)
+}
+
+/// Get the Docker health check for a database type
+///
+/// Checks go over TCP where the client allows it, so the temporary server
+/// the official images run during initialization does not count as healthy.
+/// Credentials come from the container's own environment.
+fn get_healthcheck(database_type: &DatabaseType) -> HealthConfig {
+    let test = match database_type {
+        DatabaseType::PostgreSQL => "pg_isready -U postgres -h 127.0.0.1",
+        DatabaseType::Redis => "redis-cli ping | grep -q PONG",
+        DatabaseType::Valkey => "valkey-cli ping | grep -q PONG",
+        DatabaseType::KeyDB => "keydb-cli ping | grep -q PONG",
+        DatabaseType::MySQL => {
+            "MYSQL_PWD=\"$MYSQL_ROOT_PASSWORD\" mysqladmin ping -h 127.0.0.1 -uroot --silent"
+        }
+        // MariaDB 11 dropped the mysql* client names
+        DatabaseType::MariaDB => {
+            "export MYSQL_PWD=\"$MARIADB_ROOT_PASSWORD\"; \
+             mariadb-admin ping -h 127.0.0.1 -uroot --silent || \
+             mysqladmin ping -h 127.0.0.1 -uroot --silent"
+        }
+        // Images before MongoDB 6 ship the legacy `mongo` shell instead of mongosh
+        DatabaseType::MongoDB => {
+            "(mongosh --quiet --eval 'db.adminCommand(\"ping\").ok' || \
+             mongo --quiet --eval 'db.adminCommand(\"ping\").ok') | grep -q 1"
+        }
+        // /ping answers without credentials once the server accepts queries
+        DatabaseType::ClickHouse => "wget -q --spider http://127.0.0.1:8123/ping",
+        // Credentials are ignored when security is disabled
+        DatabaseType::Elasticsearch => {
+            "curl -fs -u \"elastic:$ELASTIC_PASSWORD\" \
+             'http://127.0.0.1:9200/_cluster/health?wait_for_status=yellow&timeout=1s'"
+        }
+        DatabaseType::OpenSearch => {
+            "curl -fs -u \"admin:$OPENSEARCH_INITIAL_ADMIN_PASSWORD\" \
+             'http://127.0.0.1:9200/_cluster/health?wait_for_status=yellow&timeout=1s'"
+        }
+        // Since 2022 CU14 the tools live in mssql-tools18 and verify the server
+        // certificate unless told not to (-C)
+        DatabaseType::SqlServer => {
+            "/opt/mssql-tools18/bin/sqlcmd -C -S 127.0.0.1 -U sa -P \"$MSSQL_SA_PASSWORD\" -Q 'SELECT 1' -b -o /dev/null || \
+             /opt/mssql-tools/bin/sqlcmd -S 127.0.0.1 -U sa -P \"$MSSQL_SA_PASSWORD\" -Q 'SELECT 1' -b -o /dev/null"
+        }
+    };
+
+    const SECOND: i64 = 1_000_000_000;
+
+    HealthConfig {
+        test: Some(vec!["CMD-SHELL".to_string(), test.to_string()]),
+        interval: Some(10 * SECOND),
+        timeout: Some(5 * SECOND),
+        retries: Some(3),
+        // First-run initialization can take a while; failures meanwhile don't count
+        start_period: Some(60 * SECOND),
+        ..Default::default()
+    }
+}
+
 /// Convert bollard container state to InstanceStatus
+///
+/// Running containers map to `Ready` or `Unhealthy` when their health check
+/// has a verdict, and to `Running` (not yet probed) otherwise.
 fn get_instance_status(state: &bollard::models::ContainerState) -> InstanceStatus {
     if state.running == Some(true) {
-        InstanceStatus::Running
+        match state.health.as_ref().and_then(|h| h.status) {
+            Some(HealthStatusEnum::HEALTHY) => InstanceStatus::Ready,
+            Some(HealthStatusEnum::UNHEALTHY) => InstanceStatus::Unhealthy,
+            _ => InstanceStatus::Running,
+        }
     } else if state.paused == Some(true) {
         InstanceStatus::Stopped
     } else if state.restarting == Some(true) {
@@ -78,397 +305,646 @@ fn get_instance_status(state: &bollard::models::ContainerState) -> InstanceStatu
     }
 }
 
-/// Create a new database container (without starting it)
-/// Note: The image should be pulled separately via pull_docker_image before calling this
-#[tauri::command]
-pub async fn create_instance(request: CreateInstanceRequest) -> Result<Instance, String> {
-    let docker = Docker::connect_with_local_defaults()
-        .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
-
-    // Get port - either from request or auto-detect
-    let port = match request.port {
-        Some(p) => {
-            // Check if port is already occupied
-            let occupied = get_occupied_ports().await?;
-            if occupied.contains(&p) {
-                return Err(format!("Port {} is already in use", p));
-            }
-            p
+/// Find the stored instance a container belongs to
+///
+/// Matches on the instance-ID label; containers created before labels
+/// existed are matched by their legacy `ldb-{name}` container name.
+pub fn find_stored_instance<'a>(
+    container_labels: Option<&HashMap<String, String>>,
+    container_name: &str,
+    stored: &'a [Instance],
+) -> Option<&'a Instance> {
+    if labels::is_labeled(container_labels) {
+        let id = labels::instance_id(container_labels)?;
+        stored.iter().find(|i| i.id == id)
+    } else {
+        stored
+            .iter()
+            .find(|i| labels::legacy_container_name(&i.name) == container_name)
+    }
+}
+
+/// Resolve an instance ID to its container ID
+///
+/// Anything that is not the ID of a stored instance is passed through
+/// unchanged and treated as a container ID or name.
+async fn resolve_container_id(docker: &Docker, id: &str) -> Result<String, LdbError> {
+    if Uuid::parse_str(id).is_ok() {
+        if let Some(instance) = StateManager::new()?.get_instance(id)? {
+            return labels::find_container(docker, &instance)
+                .await?
+                .and_then(|c| c.id)
+                .ok_or_else(|| LdbError::ContainerNotFound { id: id.to_string() });
         }
-        None => {
-            // Auto-detect next available port for this database type
-            get_next_port_for_type(request.database_type).await?
+    }
+
+    Ok(id.to_string())
+}
+
+/// Pairs of container port and host port to publish for an instance
+pub fn port_mappings(instance: &Instance) -> Vec<(u16, u16)> {
+    let mut mappings = vec![(get_container_port(&instance.database_type), instance.port)];
+    for extra in get_extra_ports(&instance.database_type) {
+        if let Some(host_port) = instance.extra_ports.get(extra.name) {
+            mappings.push((extra.container_port, *host_port));
         }
-    };
+    }
+    mappings
+}
 
-    let container_name = format!("ldb-{}", request.name.replace(' ', "-").to_lowercase());
-    let full_image = format!("{}:{}", request.image, request.tag);
+/// Build the Docker config for an instance's container
+fn build_container_config(
+    instance: &Instance,
+    password: &str,
+    engine: &EngineInfo,
+) -> Result<Config<String>, LdbError> {
+    let full_image = format!("{}:{}", instance.image, instance.tag);
 
     // Get environment variables based on database type
-    let env_vars = get_env_vars(&request.database_type, &request.password);
+    let env_vars = get_env_vars(&instance.database_type, password);
     let env: Vec<String> = env_vars
         .iter()
         .map(|(k, v)| format!("{}={}", k, v))
         .collect();
 
-    // Create volume directory for this instance
-    let instance_id = Uuid::new_v4().to_string();
-    let volume_path = StateManager::get_instance_volume_path(&instance_id)?;
-    let volume_path_str = volume_path.to_string_lossy().to_string();
-    
-    // Get the container's internal volume path
-    let container_volume_path = get_volume_path(&request.database_type);
-    let volume_bind = format!("{}:{}", volume_path_str, container_volume_path);
+    // Host side of the volume: stored path, or the default per-instance directory
+    let volume_path_str = match &instance.volume_path {
+        Some(path) => path.clone(),
+        None => StateManager::get_instance_volume_path(&instance.id.to_string())?
+            .to_string_lossy()
+            .to_string(),
+    };
+
+    // Get the container's internal data directory
+    let container_volume_path = volumes::get_data_path(&instance.database_type, &instance.tag);
+    let volume_bind = format!(
+        "{}:{}{}",
+        volume_path_str,
+        container_volume_path,
+        engine.bind_options()
+    );
 
     // Get command if needed (Redis)
-    let cmd = get_database_command(&request.database_type, &request.password);
+    let cmd = get_database_command(&instance.database_type, password);
 
-    // Create port bindings
+    // Create port bindings, one per host address the instance listens on
     let mut port_bindings = HashMap::new();
-    port_bindings.insert(
-        format!("{}/tcp", port),
-        Some(vec![bollard::models::PortBinding {
-            host_ip: Some("0.0.0.0".to_string()),
-            host_port: Some(port.to_string()),
-        }])
-    );
-
-    // Create exposed ports
     let mut exposed_ports = HashMap::new();
-    exposed_ports.insert(format!("{}/tcp", port), HashMap::new());
+    for (container_port, host_port) in port_mappings(instance) {
+        let container_port = format!("{}/tcp", container_port);
+        let bindings = instance
+            .bind_address
+            .host_ips()
+            .into_iter()
+            .map(|host_ip| bollard::models::PortBinding {
+                host_ip: Some(host_ip),
+                host_port: Some(host_port.to_string()),
+            })
+            .collect();
 
-    // Create container config
-    let config = Config {
-        image: Some(full_image.clone()),
+        port_bindings.insert(container_port.clone(), Some(bindings));
+        exposed_ports.insert(container_port, HashMap::new());
+    }
+
+    Ok(Config {
+        image: Some(full_image),
         env: if env.is_empty() { None } else { Some(env) },
-        cmd: cmd.clone(),
+        cmd,
+        user: get_container_user(&instance.database_type).map(str::to_string),
         host_config: Some(bollard::models::HostConfig {
             port_bindings: Some(port_bindings),
             binds: Some(vec![volume_bind]),
             ..Default::default()
         }),
         exposed_ports: Some(exposed_ports),
+        healthcheck: Some(get_healthcheck(&instance.database_type)),
+        labels: Some(labels::for_instance(&instance.id, &instance.database_type)),
         ..Default::default()
-    };
+    })
+}
+
+/// Create the container for an instance, returning the container name
+pub async fn create_container(docker: &Docker, instance: &Instance, password: &str) -> Result<String, LdbError> {
+    let container_name = labels::container_name(&instance.name, &instance.id);
+    let engine = engine::detect(docker).await?;
+    let config = build_container_config(instance, password, &engine)?;
 
     let options = CreateContainerOptions {
         name: container_name.clone(),
         platform: None,
     };
 
-    // Note: Image should already be pulled via pull_docker_image before this call
-    // We don't pull here to allow the frontend to show progress
-
-    // Create the container
     let response = docker
         .create_container(Some(options), config)
         .await
-        .map_err(|e| format!("Failed to create container: {}", e))?;
+        .map_err(|e| match e {
+            bollard::errors::Error::DockerResponseServerError { status_code: 404, .. } => {
+                LdbError::ImageMissing { image: format!("{}:{}", instance.image, instance.tag) }
+            }
+            e => LdbError::docker("Failed to create container", e),
+        })?;
+
+    println!(
+        "Created container {} with ID {}",
+        container_name, response.id
+    );
+
+    Ok(container_name)
+}
+
+/// Create a new database container (without starting it)
+/// Note: The image should be pulled separately via pull_docker_image before calling this
+#[tauri::command]
+pub async fn create_instance(
+    client: State<'_, DockerClient>,
+    request: CreateInstanceRequest,
+) -> Result<Instance, LdbError> {
+    validate_password(&request.database_type, &request.password)?;
+
+    let docker = client.connection().await?;
+
+    // Requested ports are checked, the rest auto-detected for this database type
+    let mut requested = request.ports;
+    if let Some(port) = request.port {
+        requested.insert(PRIMARY_PORT.to_string(), port);
+    }
+    let ports = allocate_named_ports(&docker, &request.database_type, &requested).await?;
 
     let mut instance = Instance::new(
         request.name,
         request.database_type,
         request.image,
         request.tag,
-        port,
-        request.password,
+        0,
     );
-    
-    // Set the instance ID to match what we created for the volume
-    instance.id = Uuid::parse_str(&instance_id).map_err(|e| format!("Failed to parse instance ID: {}", e))?;
-    instance.volume_path = Some(volume_path_str);
+
+    instance.bind_address = request.bind_address;
+    instance.set_ports(ports);
+
+    // Create volume directory for this instance
+    let volume_path = StateManager::get_instance_volume_path(&instance.id.to_string())?;
+    instance.volume_path = Some(volume_path.to_string_lossy().to_string());
+
+    // Note: Image should already be pulled via pull_docker_image before this call
+    // We don't pull here to allow the frontend to show progress
+    let data_path = volumes::get_data_path(&instance.database_type, &instance.tag);
+    volumes::validate_data_path(&docker, &format!("{}:{}", instance.image, instance.tag), data_path).await?;
+
+    let container_name = create_container(&docker, &instance, &request.password).await?;
+    instance.container_name = Some(container_name);
+
+    // Keep the password in the secret store; state only holds a reference
+    if !request.password.is_empty() {
+        let key = secrets::instance_password_key(&instance.id.to_string());
+        instance.password_ref = Some(secrets::store(&key, &request.password)?);
+    }
 
     // Persist instance state
     let state_manager = StateManager::new()?;
     state_manager.add_instance(instance.clone())?;
 
-    println!(
-        "Created container {} with ID {}",
-        container_name, response.id
-    );
-
     Ok(instance)
 }
 
-/// Start a container
+/// Recreate containers whose port mapping targets the host port instead of the database port
+///
+/// Containers created by older versions bound `{host_port}/tcp` inside the
+/// container, where nothing listens on non-default ports. Each affected
+/// container is rebuilt from its own config with only the mapping moved,
+/// keeping its data, environment and networks. Returns the instances that
+/// were repaired.
 #[tauri::command]
-pub async fn start_instance(container_id: String) -> Result<Instance, String> {
-    let docker = Docker::connect_with_local_defaults()
-        .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
+pub async fn migrate_port_mappings(client: State<'_, DockerClient>) -> Result<Vec<Instance>, LdbError> {
+    let docker = client.connection().await?;
 
-    docker
-        .start_container(&container_id, None::<StartContainerOptions<String>>)
-        .await
-        .map_err(|e| format!("Failed to start container: {}", e))?;
+    let state_manager = StateManager::new()?;
+    let mut repaired = Vec::new();
 
-    // Inspect to get container details
-    let info = docker
-        .inspect_container(&container_id, None)
-        .await
-        .map_err(|e| format!("Failed to inspect container: {}", e))?;
+    for instance in state_manager.load_instances()? {
+        let Some(container) = labels::find_container(&docker, &instance).await? else {
+            continue;
+        };
 
-    let config = info.config.ok_or("No config found")?;
-    let state = info.state.ok_or("No state found")?;
+        let info = docker
+            .inspect_container(&container.id.unwrap_or_default(), None)
+            .await
+            .map_err(|e| LdbError::docker("Failed to inspect container", e))?;
 
-    // Parse database type from image name
-    let image = config.image.unwrap_or_default();
-    let database_type = if image.contains("postgres") {
+        let port_bindings = info
+            .host_config
+            .as_ref()
+            .and_then(|hc| hc.port_bindings.clone())
+            .unwrap_or_default();
+        let misbound = port_mappings(&instance).into_iter().any(|(container_port, host_port)| {
+            let bound_correctly = port_bindings
+                .get(&format!("{}/tcp", container_port))
+                .cloned()
+                .flatten()
+                .is_some_and(|bindings| {
+                    bindings
+                        .iter()
+                        .any(|b| b.host_port.as_deref() == Some(&host_port.to_string()))
+                });
+            !bound_correctly && port_bindings.contains_key(&format!("{}/tcp", host_port))
+        });
+
+        if !misbound {
+            continue;
+        }
+
+        reconcile::replace_container(&docker, &instance, info).await?;
+        repaired.push(instance);
+    }
+
+    Ok(repaired)
+}
+
+/// Look up a stored instance and its container ID
+async fn find_instance_container(
+    docker: &Docker,
+    state_manager: &StateManager,
+    instance_id: &str,
+) -> Result<(Instance, String), LdbError> {
+    let instance = state_manager
+        .get_instance(instance_id)?
+        .ok_or_else(|| LdbError::InstanceNotFound { id: instance_id.to_string() })?;
+
+    let container_id = labels::find_container(docker, &instance)
+        .await?
+        .and_then(|c| c.id)
+        .ok_or_else(|| LdbError::ContainerNotFound { id: instance_id.to_string() })?;
+
+    Ok((instance, container_id))
+}
+
+/// Infer the database type of a container we have no stored record for
+fn detect_database_type(container_labels: Option<&HashMap<String, String>>, image: &str) -> DatabaseType {
+    let labeled = container_labels
+        .and_then(|l| l.get(labels::LABEL_DATABASE_TYPE))
+        .and_then(|t| serde_json::from_value(serde_json::Value::String(t.clone())).ok());
+
+    if let Some(database_type) = labeled {
+        return database_type;
+    }
+
+    if image.contains("postgres") {
         DatabaseType::PostgreSQL
+    } else if image.contains("valkey") {
+        DatabaseType::Valkey
+    } else if image.contains("keydb") {
+        DatabaseType::KeyDB
     } else if image.contains("redis") {
         DatabaseType::Redis
+    } else if image.contains("mariadb") {
+        DatabaseType::MariaDB
     } else if image.contains("mysql") {
         DatabaseType::MySQL
     } else if image.contains("mongo") {
         DatabaseType::MongoDB
+    } else if image.contains("clickhouse") {
+        DatabaseType::ClickHouse
+    } else if image.contains("opensearch") {
+        DatabaseType::OpenSearch
+    } else if image.contains("elasticsearch") {
+        DatabaseType::Elasticsearch
+    } else if image.contains("mssql") {
+        DatabaseType::SqlServer
     } else {
         DatabaseType::PostgreSQL
-    };
+    }
+}
 
-    let name = info
+/// Build the instance record for a container from its inspect data
+///
+/// Fields of the stored instance win; the container supplies the live status
+/// and name, and fills in everything else for containers we have no record of.
+pub fn reconcile_instance(
+    id: Uuid,
+    stored: Option<&Instance>,
+    info: ContainerInspectResponse,
+) -> Result<Instance, LdbError> {
+    let config = info.config.ok_or_else(|| LdbError::docker("Failed to inspect container", "no config found"))?;
+    let state = info.state.ok_or_else(|| LdbError::docker("Failed to inspect container", "no state found"))?;
+
+    let container_name = info
         .name
         .unwrap_or_default()
         .trim_start_matches('/')
         .to_string();
+    let status = get_instance_status(&state);
+
+    if let Some(stored) = stored {
+        return Ok(Instance {
+            status,
+            container_name: Some(container_name),
+            ..stored.clone()
+        });
+    }
+
+    let image = config.image.unwrap_or_default();
+    let (image_name, tag) = image.rsplit_once(':').unwrap_or((&image, "latest"));
+    let database_type = detect_database_type(config.labels.as_ref(), &image);
+
+    // Host ports published for the database's ports; stopped containers only
+    // have them in their host config
+    let published = info
+        .network_settings
+        .and_then(|ns| ns.ports)
+        .filter(|ports| ports.values().any(Option::is_some));
+    let port_map = published
+        .or_else(|| info.host_config.and_then(|hc| hc.port_bindings))
+        .unwrap_or_default();
+    let bindings_for = |container_port: u16| {
+        port_map
+            .get(&format!("{}/tcp", container_port))
+            .cloned()
+            .flatten()
+            .unwrap_or_default()
+    };
+    let host_port = |bindings: &[PortBinding]| -> Option<u16> {
+        bindings.iter().find_map(|b| b.host_port.as_ref()?.parse().ok())
+    };
 
-    let created_at = info.created.map(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
-        .flatten()
+    let bindings = bindings_for(get_container_port(&database_type));
+    let port = host_port(&bindings).unwrap_or(0);
+    let host_ips: Vec<String> = bindings.into_iter().filter_map(|b| b.host_ip).collect();
+    let extra_ports = get_extra_ports(&database_type)
+        .iter()
+        .filter_map(|extra| Some((extra.name.to_string(), host_port(&bindings_for(extra.container_port))?)))
+        .collect();
+
+    let created_at = info
+        .created
+        .and_then(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
         .map(|dt| dt.with_timezone(&Utc))
         .unwrap_or_else(Utc::now);
 
-    // Try to get volume path from state
-    let state_manager = StateManager::new().ok();
-    let volume_path = state_manager
-        .and_then(|sm| {
-            let instances = sm.load_instances().ok()?;
-            instances.into_iter().find(|i| {
-                let container_name = format!("ldb-{}", i.name.replace(' ', "-").to_lowercase());
-                container_name == name
-            })
-        })
-        .and_then(|i| i.volume_path);
-
-    // Get port from network settings
-    let port = info.network_settings
-        .as_ref()
-        .and_then(|ns| ns.ports.as_ref())
-        .and_then(|ports| {
-            ports.iter().next().and_then(|(_port_key, bindings)| {
-                bindings.as_ref().and_then(|bindings| {
-                    bindings.first().and_then(|b| {
-                        b.host_port.as_ref().and_then(|p| p.parse().ok())
-                    })
-                })
-            })
-        })
-        .unwrap_or(0);
-
-    let instance = Instance {
-        id: Uuid::new_v4(),
-        name,
+    Ok(Instance {
+        id,
+        name: container_name.clone(),
         database_type,
-        image: image.clone(),
-        tag: image.split(':').nth(1).unwrap_or("latest").to_string(),
+        image: image_name.to_string(),
+        tag: tag.to_string(),
         port,
-        root_password: String::new(), // Not retrievable from inspect
-        status: get_instance_status(&state),
+        bind_address: BindAddress::from_host_ips(&host_ips),
+        extra_ports,
+        password_ref: None,
+        status,
         created_at,
-        volume_path,
-    };
-
-    Ok(instance)
+        volume_path: None,
+        adopted_mount: false,
+        container_name: Some(container_name),
+    })
 }
 
-/// Stop a container
-#[tauri::command]
-pub async fn stop_instance(container_id: String) -> Result<Instance, String> {
-    let docker = Docker::connect_with_local_defaults()
-        .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
-
-    let options = StopContainerOptions {
-        t: 10, // 10 second timeout
-    };
+/// Persist an instance's new status and tell the UI when it changed
+///
+/// Only the status column is written; `stored` may be stale by now.
+fn save_status(
+    app: &AppHandle,
+    state_manager: &StateManager,
+    stored: &Instance,
+    updated: Instance,
+    message: Option<String>,
+) -> Result<Instance, LdbError> {
+    if updated.status != stored.status {
+        state_manager.modify_instance(&updated.id.to_string(), |i| i.status = updated.status)?;
+        events::emit_status(app, &updated, message);
+    }
 
-    docker
-        .stop_container(&container_id, Some(options))
-        .await
-        .map_err(|e| format!("Failed to stop container: {}", e))?;
+    Ok(updated)
+}
 
-    // Inspect to get container details
+/// Inspect a stored instance's container and persist its observed status
+///
+/// Docker only knows whether the container runs; for running containers the
+/// database is probed once to tell `Starting` from `Ready`.
+async fn sync_instance(
+    app: &AppHandle,
+    docker: &Docker,
+    state_manager: &StateManager,
+    instance: &Instance,
+    container_id: &str,
+) -> Result<Instance, LdbError> {
     let info = docker
-        .inspect_container(&container_id, None)
+        .inspect_container(container_id, None)
         .await
-        .map_err(|e| format!("Failed to inspect container: {}", e))?;
+        .map_err(|e| LdbError::docker(&format!("Failed to inspect container {}", container_id), e))?;
 
-    let config = info.config.ok_or("No config found")?;
+    let mut reconciled = reconcile_instance(instance.id, Some(instance), info)?;
 
-    // Parse database type from image name
-    let image = config.image.unwrap_or_default();
-    let database_type = if image.contains("postgres") {
-        DatabaseType::PostgreSQL
-    } else if image.contains("redis") {
-        DatabaseType::Redis
-    } else if image.contains("mysql") {
-        DatabaseType::MySQL
-    } else if image.contains("mongo") {
-        DatabaseType::MongoDB
-    } else {
-        DatabaseType::PostgreSQL
-    };
+    // Without a health check verdict, probe the database ourselves
+    if reconciled.status == InstanceStatus::Running {
+        reconciled.status = if instance.status == InstanceStatus::Ready {
+            InstanceStatus::Ready
+        } else {
+            let password = secrets::resolve(instance.password_ref.as_ref()).unwrap_or_default();
+            if readiness::probe(&reconciled, &password).await {
+                InstanceStatus::Ready
+            } else {
+                InstanceStatus::Starting
+            }
+        };
+    }
 
-    let name = info
-        .name
-        .unwrap_or_default()
-        .trim_start_matches('/')
-        .to_string();
+    save_status(app, state_manager, instance, reconciled, None)
+}
 
-    let created_at = info.created.map(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
-        .flatten()
-        .map(|dt| dt.with_timezone(&Utc))
-        .unwrap_or_else(Utc::now);
+/// Mark a just-started instance as `Starting` and wait for its database
+///
+/// Returns the `Ready` instance, or `NotReady` once the timeout runs out;
+/// the instance then keeps whatever status Docker reports.
+async fn wait_for_ready(
+    app: &AppHandle,
+    docker: &Docker,
+    state_manager: &StateManager,
+    instance: &Instance,
+    container_id: &str,
+    timeout_secs: Option<u64>,
+) -> Result<Instance, LdbError> {
+    let starting = Instance {
+        status: InstanceStatus::Starting,
+        ..instance.clone()
+    };
+    let starting = save_status(app, state_manager, instance, starting, None)?;
 
-    // Try to get volume path from state
-    let state_manager = StateManager::new().ok();
-    let volume_path = state_manager
-        .and_then(|sm| {
-            let instances = sm.load_instances().ok()?;
-            instances.into_iter().find(|i| {
-                let container_name = format!("ldb-{}", i.name.replace(' ', "-").to_lowercase());
-                container_name == name
-            })
-        })
-        .and_then(|i| i.volume_path);
-
-    // Get port from network settings
-    let port = info.network_settings
-        .as_ref()
-        .and_then(|ns| ns.ports.as_ref())
-        .and_then(|ports| {
-            ports.iter().next().and_then(|(_port_key, bindings)| {
-                bindings.as_ref().and_then(|bindings| {
-                    bindings.first().and_then(|b| {
-                        b.host_port.as_ref().and_then(|p| p.parse().ok())
-                    })
-                })
-            })
-        })
-        .unwrap_or(0);
+    let password = secrets::resolve(instance.password_ref.as_ref())?;
+    let wait = timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(readiness::DEFAULT_TIMEOUT);
 
-    let instance = Instance {
-        id: Uuid::new_v4(),
-        name,
-        database_type,
-        image: image.clone(),
-        tag: image.split(':').nth(1).unwrap_or("latest").to_string(),
-        port,
-        root_password: String::new(),
-        status: InstanceStatus::Stopped,
-        created_at,
-        volume_path,
-    };
+    if let Err(e) = readiness::wait_until_ready(&starting, &password, wait).await {
+        // The container may have exited in the meantime
+        sync_instance(app, docker, state_manager, &starting, container_id).await?;
+        return Err(e);
+    }
 
-    Ok(instance)
+    let ready = Instance {
+        status: InstanceStatus::Ready,
+        ..starting.clone()
+    };
+    save_status(app, state_manager, &starting, ready, None)
 }
 
-/// Restart a container
+/// Start an instance's container and wait until its database accepts connections
+///
+/// If another process took one of the instance's ports while it was stopped,
+/// this fails with `PortConflict` and a suggested free port, unless
+/// `rebind_port` is set, in which case every taken port moves first.
 #[tauri::command]
-pub async fn restart_instance(container_id: String) -> Result<Instance, String> {
-    let docker = Docker::connect_with_local_defaults()
-        .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
+pub async fn start_instance(
+    app: AppHandle,
+    client: State<'_, DockerClient>,
+    instance_id: String,
+    ready_timeout_secs: Option<u64>,
+    rebind_port: Option<bool>,
+) -> Result<Instance, LdbError> {
+    let docker = client.connection().await?;
+    let state_manager = StateManager::new()?;
+    let (mut instance, mut container_id) = find_instance_container(&docker, &state_manager, &instance_id).await?;
 
-    let options = bollard::container::RestartContainerOptions {
-        t: 10, // 10 second timeout
+    let running = docker
+        .inspect_container(&container_id, None)
+        .await
+        .map_err(|e| LdbError::docker("Failed to inspect container", e))?
+        .state
+        .and_then(|s| s.running)
+        == Some(true);
+
+    // A running container holds its own ports
+    let moved = if running {
+        Default::default()
+    } else {
+        conflicting_ports(&docker, &instance).await?
     };
+    if let Some((name, &suggested_port)) = moved.iter().next() {
+        if !rebind_port.unwrap_or(false) {
+            return Err(LdbError::PortConflict {
+                id: instance.id.to_string(),
+                port: instance.ports()[name],
+                suggested_port,
+            });
+        }
+
+        instance = rebind(&app, &docker, &state_manager, instance, moved).await?;
+        container_id = instance.container_name.clone().unwrap_or_default();
+    }
 
     docker
-        .restart_container(&container_id, Some(options))
+        .start_container(&container_id, None::<StartContainerOptions<String>>)
         .await
-        .map_err(|e| format!("Failed to restart container: {}", e))?;
+        .map_err(|e| {
+            // Lost a race for the port after the check above
+            let message = e.to_string();
+            if message.contains("port is already allocated") || message.contains("address already in use") {
+                LdbError::PortInUse { port: instance.port }
+            } else {
+                LdbError::docker("Failed to start container", e)
+            }
+        })?;
 
-    // Wait a moment for container to be fully running
-    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+    wait_for_ready(&app, &docker, &state_manager, &instance, &container_id, ready_timeout_secs).await
+}
 
-    // Inspect to get container details
+/// Move a stopped instance to other host ports, keeping its data
+///
+/// `moved` holds the new ports by name. Ports are part of the container's
+/// config, so the container is rebuilt from its inspected config with only
+/// the port bindings changed; adopted containers keep their environment,
+/// command, networks and mounts as they were. Emits `instance-port-changed`
+/// with the new connection string.
+async fn rebind(
+    app: &AppHandle,
+    docker: &Docker,
+    state_manager: &StateManager,
+    instance: Instance,
+    moved: BTreeMap<String, u16>,
+) -> Result<Instance, LdbError> {
+    let mut rebound = instance;
+    let mut ports = rebound.ports();
+    ports.extend(moved);
+    rebound.set_ports(ports);
+
+    let container = labels::find_container(docker, &rebound)
+        .await?
+        .ok_or_else(|| LdbError::ContainerNotFound { id: rebound.id.to_string() })?;
     let info = docker
-        .inspect_container(&container_id, None)
+        .inspect_container(&container.id.unwrap_or_default(), None)
         .await
-        .map_err(|e| format!("Failed to inspect container: {}", e))?;
+        .map_err(|e| LdbError::docker("Failed to inspect container", e))?;
+    rebound.container_name = Some(reconcile::replace_container(docker, &rebound, info).await?);
+
+    let ports = rebound.ports();
+    state_manager.modify_instance(&rebound.id.to_string(), |i| i.set_ports(ports))?;
+
+    let password = secrets::resolve(rebound.password_ref.as_ref())?;
+    let _ = app.emit(
+        "instance-port-changed",
+        InstancePortChangedEvent {
+            instance_id: rebound.id,
+            port: rebound.port,
+            ports: rebound.ports(),
+            connection_string: connections::connection_string(&rebound, &password),
+        },
+    );
 
-    let config = info.config.ok_or("No config found")?;
-    let state = info.state.ok_or("No state found")?;
+    Ok(rebound)
+}
 
-    // Parse database type from image name
-    let image = config.image.unwrap_or_default();
-    let database_type = if image.contains("postgres") {
-        DatabaseType::PostgreSQL
-    } else if image.contains("redis") {
-        DatabaseType::Redis
-    } else if image.contains("mysql") {
-        DatabaseType::MySQL
-    } else if image.contains("mongo") {
-        DatabaseType::MongoDB
-    } else {
-        DatabaseType::PostgreSQL
+/// Stop an instance's container
+#[tauri::command]
+pub async fn stop_instance(
+    app: AppHandle,
+    client: State<'_, DockerClient>,
+    instance_id: String,
+) -> Result<Instance, LdbError> {
+    let docker = client.connection().await?;
+    let state_manager = StateManager::new()?;
+    let (instance, container_id) = find_instance_container(&docker, &state_manager, &instance_id).await?;
+
+    let options = StopContainerOptions {
+        t: 10, // 10 second timeout
     };
 
-    let name = info
-        .name
-        .unwrap_or_default()
-        .trim_start_matches('/')
-        .to_string();
+    docker
+        .stop_container(&container_id, Some(options))
+        .await
+        .map_err(|e| LdbError::docker("Failed to stop container", e))?;
 
-    let created_at = info.created.map(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
-        .flatten()
-        .map(|dt| dt.with_timezone(&Utc))
-        .unwrap_or_else(Utc::now);
+    sync_instance(&app, &docker, &state_manager, &instance, &container_id).await
+}
 
-    // Try to get volume path from state
-    let state_manager = StateManager::new().ok();
-    let volume_path = state_manager
-        .and_then(|sm| {
-            let instances = sm.load_instances().ok()?;
-            instances.into_iter().find(|i| {
-                let container_name = format!("ldb-{}", i.name.replace(' ', "-").to_lowercase());
-                container_name == name
-            })
-        })
-        .and_then(|i| i.volume_path);
-
-    // Get port from network settings
-    let port = info.network_settings
-        .as_ref()
-        .and_then(|ns| ns.ports.as_ref())
-        .and_then(|ports| {
-            ports.iter().next().and_then(|(_port_key, bindings)| {
-                bindings.as_ref().and_then(|bindings| {
-                    bindings.first().and_then(|b| {
-                        b.host_port.as_ref().and_then(|p| p.parse().ok())
-                    })
-                })
-            })
-        })
-        .unwrap_or(0);
+/// Restart an instance's container and wait until its database accepts connections
+#[tauri::command]
+pub async fn restart_instance(
+    app: AppHandle,
+    client: State<'_, DockerClient>,
+    instance_id: String,
+    ready_timeout_secs: Option<u64>,
+) -> Result<Instance, LdbError> {
+    let docker = client.connection().await?;
+    let state_manager = StateManager::new()?;
+    let (instance, container_id) = find_instance_container(&docker, &state_manager, &instance_id).await?;
 
-    let instance = Instance {
-        id: Uuid::new_v4(),
-        name,
-        database_type,
-        image: image.clone(),
-        tag: image.split(':').nth(1).unwrap_or("latest").to_string(),
-        port,
-        root_password: String::new(),
-        status: get_instance_status(&state),
-        created_at,
-        volume_path,
+    let options = bollard::container::RestartContainerOptions {
+        t: 10, // 10 second timeout
     };
 
-    Ok(instance)
+    docker
+        .restart_container(&container_id, Some(options))
+        .await
+        .map_err(|e| LdbError::docker("Failed to restart container", e))?;
+
+    wait_for_ready(&app, &docker, &state_manager, &instance, &container_id, ready_timeout_secs).await
 }
 
 /// List all containers (including stopped)
 #[tauri::command]
-pub async fn list_instances() -> Result<Vec<Instance>, String> {
-    let docker = Docker::connect_with_local_defaults()
-        .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
+pub async fn list_instances(
+    app: AppHandle,
+    client: State<'_, DockerClient>,
+) -> Result<Vec<Instance>, LdbError> {
+    let docker = client.connection().await?;
 
     let options = Some(ListContainersOptions::<String> {
         all: true, // Include stopped containers
@@ -478,23 +954,11 @@ pub async fn list_instances() -> Result<Vec<Instance>, String> {
     let containers = docker
         .list_containers(options)
         .await
-        .map_err(|e| format!("Failed to list containers: {}", e))?;
+        .map_err(|e| LdbError::docker("Failed to list containers", e))?;
 
     // Load stored instances from state to get correct IDs and passwords
-    let state_manager = StateManager::new().ok();
-    let stored_instances: std::collections::HashMap<String, Instance> = state_manager
-        .as_ref()
-        .and_then(|sm| sm.load_instances().ok())
-        .map(|instances| {
-            instances
-                .into_iter()
-                .map(|i| {
-                    let container_name = format!("ldb-{}", i.name.replace(' ', "-").to_lowercase());
-                    (container_name, i)
-                })
-                .collect()
-        })
-        .unwrap_or_default();
+    let state_manager = StateManager::new()?;
+    let stored_instances = state_manager.load_instances().unwrap_or_default();
 
     let mut instances = Vec::new();
 
@@ -505,58 +969,25 @@ pub async fn list_instances() -> Result<Vec<Instance>, String> {
             .map(|n| n.trim_start_matches('/').to_string())
             .unwrap_or_default();
 
-        // Only include containers we created (prefixed with ldb-)
-        if !name.starts_with("ldb-") {
+        // Only include containers we created: labeled ones, or unlabeled
+        // ones from older versions that match a stored instance
+        let stored = find_stored_instance(container.labels.as_ref(), &name, &stored_instances);
+        let label_id = labels::instance_id(container.labels.as_ref());
+        let Some(id) = stored.map(|i| i.id).or(label_id) else {
             continue;
-        }
+        };
 
         let container_id = container.id.unwrap_or_default();
 
-        // Inspect each container to get more details
-        let info = docker
-            .inspect_container(&container_id, None)
-            .await
-            .map_err(|e| format!("Failed to inspect container {}: {}", container_id, e))?;
-
-        let config = info.config.ok_or("No config found")?;
-        let state = info.state.ok_or("No state found")?;
-
-        // Parse database type from image name
-        let image = config.image.unwrap_or_default();
-        let database_type = if image.contains("postgres") {
-            DatabaseType::PostgreSQL
-        } else if image.contains("redis") {
-            DatabaseType::Redis
-        } else if image.contains("mysql") {
-            DatabaseType::MySQL
-        } else if image.contains("mongo") {
-            DatabaseType::MongoDB
-        } else {
-            DatabaseType::PostgreSQL
-        };
-
-        let created_at = info.created.map(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
-            .flatten()
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(Utc::now);
-
-        // Look up stored instance by container name to get correct ID and password
-        let stored = stored_instances.get(&name);
-        
-        let instance = Instance {
-            id: stored.map(|i| i.id).unwrap_or_else(Uuid::new_v4),
-            name: stored.map(|i| i.name.clone()).unwrap_or_else(|| name.clone()),
-            database_type,
-            image: image.clone(),
-            tag: image.split(':').nth(1).unwrap_or("latest").to_string(),
-            port: container.ports.as_ref()
-                .and_then(|ports| ports.first())
-                .and_then(|p| p.public_port)
-                .unwrap_or(0),
-            root_password: stored.map(|i| i.root_password.clone()).unwrap_or_default(),
-            status: get_instance_status(&state),
-            created_at: stored.map(|i| i.created_at).unwrap_or(created_at),
-            volume_path: stored.and_then(|i| i.volume_path.clone()),
+        let instance = match stored {
+            Some(stored) => sync_instance(&app, &docker, &state_manager, stored, &container_id).await?,
+            None => {
+                let info = docker
+                    .inspect_container(&container_id, None)
+                    .await
+                    .map_err(|e| LdbError::docker(&format!("Failed to inspect container {}", container_id), e))?;
+                reconcile_instance(id, None, info)?
+            }
         };
 
         instances.push(instance);
@@ -567,19 +998,27 @@ pub async fn list_instances() -> Result<Vec<Instance>, String> {
 
 /// Get the current status of a container
 #[tauri::command]
-pub async fn get_container_status_string(container_id: String) -> Result<String, String> {
-    let docker = Docker::connect_with_local_defaults()
-        .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
+pub async fn get_container_status_string(
+    client: State<'_, DockerClient>,
+    container_id: String,
+) -> Result<String, LdbError> {
+    let docker = client.connection().await?;
+
+    let container_id = resolve_container_id(&docker, &container_id).await?;
 
     let info = docker
         .inspect_container(&container_id, None)
         .await
-        .map_err(|e| format!("Failed to inspect container: {}", e))?;
+        .map_err(|e| LdbError::docker("Failed to inspect container", e))?;
 
-    let state = info.state.ok_or("No state found")?;
+    let state = info.state.ok_or_else(|| LdbError::docker("Failed to inspect container", "no state found"))?;
 
     if state.running == Some(true) {
-        Ok("running".to_string())
+        match state.health.as_ref().and_then(|h| h.status) {
+            Some(HealthStatusEnum::HEALTHY) => Ok("healthy".to_string()),
+            Some(HealthStatusEnum::UNHEALTHY) => Ok("unhealthy".to_string()),
+            _ => Ok("running".to_string()),
+        }
     } else if state.paused == Some(true) {
         Ok("paused".to_string())
     } else if state.restarting == Some(true) {
@@ -599,21 +1038,32 @@ pub async fn get_container_status_string(container_id: String) -> Result<String,
 
 /// Delete a container
 #[tauri::command]
-pub async fn delete_instance(container_id: String, delete_volume: Option<bool>) -> Result<(), String> {
-    let docker = Docker::connect_with_local_defaults()
-        .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
+pub async fn delete_instance(
+    client: State<'_, DockerClient>,
+    container_id: String,
+    delete_volume: Option<bool>,
+) -> Result<(), LdbError> {
+    let docker = client.connection().await?;
+
+    let container_id = resolve_container_id(&docker, &container_id).await?;
 
     // First, try to get the instance from state to find volume path
     let state_manager = StateManager::new()?;
     let instances = state_manager.load_instances()?;
-    
-    // Find instance by container_id (name starts with ldb-)
-    let instance_to_delete = instances.iter().find(|i| {
-        let container_name = format!("ldb-{}", i.name.replace(' ', "-").to_lowercase());
-        container_name == container_id
-    });
 
-    let volume_path = instance_to_delete.and_then(|i| i.volume_path.clone());
+    // Find the stored instance through the container's labels
+    let info = docker
+        .inspect_container(&container_id, None)
+        .await
+        .map_err(|e| LdbError::docker("Failed to inspect container", e))?;
+    let container_name = info
+        .name
+        .unwrap_or_default()
+        .trim_start_matches('/')
+        .to_string();
+    let container_labels = info.config.and_then(|c| c.labels);
+    let instance_to_delete =
+        find_stored_instance(container_labels.as_ref(), &container_name, &instances);
 
     let options = RemoveContainerOptions {
         force: true,
@@ -623,23 +1073,28 @@ pub async fn delete_instance(container_id: String, delete_volume: Option<bool>)
     docker
         .remove_container(&container_id, Some(options))
         .await
-        .map_err(|e| format!("Failed to delete container: {}", e))?;
+        .map_err(|e| LdbError::docker("Failed to delete container", e))?;
 
-    // Remove from state
+    // Remove from state; the container is gone, so a failure here must
+    // reach the UI rather than leave a stale row or password behind
     if let Some(instance) = instance_to_delete {
-        let _ = state_manager.remove_instance(&instance.id.to_string());
+        state_manager.remove_instance(&instance.id.to_string())?;
+
+        if let Some(secret_ref) = &instance.password_ref {
+            secrets::delete(secret_ref)?;
+        }
     }
 
-    // Delete volume directory if requested
+    // Delete volume directory if requested. Only the directory the app
+    // created is removed; an adopted container's bind mount or named volume
+    // belongs to whoever set it up.
     if delete_volume.unwrap_or(false) {
-        if let Some(vp) = volume_path {
-            if std::path::Path::new(&vp).exists() {
-                std::fs::remove_dir_all(&vp)
-                    .map_err(|e| format!("Failed to delete volume directory: {}", e))?;
+        if let Some(instance) = instance_to_delete.filter(|i| !i.adopted_mount) {
+            let path = StateManager::get_volume_dir()?.join(instance.id.to_string());
+            if path.is_dir() {
+                let engine = engine::detect(&docker).await?;
+                engine::remove_volume_dir(&path, &engine)?;
             }
-        } else if let Some(instance) = instance_to_delete {
-            // Fallback: try to delete by instance ID
-            let _ = crate::state::remove_volume_dir(&instance.id.to_string());
         }
     }
 
@@ -648,7 +1103,29 @@ pub async fn delete_instance(container_id: String, delete_volume: Option<bool>)
 
 /// Get the volume path for a specific instance
 #[tauri::command]
-pub fn get_instance_volume_path(instance_id: String) -> Result<String, String> {
+pub fn get_instance_volume_path(instance_id: String) -> Result<String, LdbError> {
     let path = StateManager::get_instance_volume_path(&instance_id)?;
     Ok(path.to_string_lossy().to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_password_enforces_sql_server_policy() {
+        assert!(validate_password(&DatabaseType::SqlServer, "Passw0rd").is_ok());
+        assert!(validate_password(&DatabaseType::SqlServer, "password1!").is_ok());
+        assert!(validate_password(&DatabaseType::SqlServer, "Pa0!").is_err());
+        assert!(validate_password(&DatabaseType::SqlServer, "password").is_err());
+        assert!(validate_password(&DatabaseType::PostgreSQL, "x").is_ok());
+    }
+
+    #[test]
+    fn validate_password_enforces_opensearch_policy() {
+        assert!(validate_password(&DatabaseType::OpenSearch, "").is_ok());
+        assert!(validate_password(&DatabaseType::OpenSearch, "Passw0rd!").is_ok());
+        assert!(validate_password(&DatabaseType::OpenSearch, "Passw0rd").is_err());
+        assert!(validate_password(&DatabaseType::OpenSearch, "Pa0!").is_err());
+    }
+}
@@ -1,8 +1,11 @@
 use bollard::container::{LogOutput, LogsOptions};
-use bollard::Docker;
 use futures::StreamExt;
 use serde::Serialize;
 use tauri::ipc::Channel;
+use tauri::State;
+
+use crate::docker::DockerClient;
+use crate::error::LdbError;
 
 #[derive(Clone, Serialize)]
 #[serde(tag = "type", content = "data")]
@@ -15,12 +18,12 @@ pub enum LogEvent {
 
 #[tauri::command]
 pub async fn stream_container_logs(
+    client: State<'_, DockerClient>,
     container_name: String,
     on_log: Channel<LogEvent>,
     tail: Option<u64>,
-) -> Result<(), String> {
-    let docker = Docker::connect_with_local_defaults()
-        .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
+) -> Result<(), LdbError> {
+    let docker = client.connection().await?;
 
     let options = LogsOptions::<String> {
         stdout: true,
@@ -39,13 +42,13 @@ pub async fn stream_container_logs(
                 let msg = String::from_utf8_lossy(&message).to_string();
                 on_log
                     .send(LogEvent::StdOut { message: msg })
-                    .map_err(|e| format!("Channel send error: {}", e))?;
+                    .map_err(|e| LdbError::internal("Channel send error", e))?;
             }
             Ok(LogOutput::StdErr { message }) => {
                 let msg = String::from_utf8_lossy(&message).to_string();
                 on_log
                     .send(LogEvent::StdErr { message: msg })
-                    .map_err(|e| format!("Channel send error: {}", e))?;
+                    .map_err(|e| LdbError::internal("Channel send error", e))?;
             }
             Ok(_) => continue, // StdIn, Console - skip
             Err(e) => {
@@ -1,5 +1,8 @@
 pub mod connections;
+pub mod docker;
 pub mod images;
 pub mod instances;
 pub mod logs;
 pub mod ports;
+pub mod reconcile;
+pub mod volumes;
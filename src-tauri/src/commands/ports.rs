@@ -1,30 +1,131 @@
+use std::collections::{BTreeMap, HashSet};
+use std::net::{Ipv4Addr, TcpListener};
+
 use bollard::Docker;
 use bollard::container::ListContainersOptions;
+use serde::{Deserialize, Serialize};
+use tauri::State;
 
-use crate::models::instance::DatabaseType;
+use crate::docker::DockerClient;
+use crate::error::LdbError;
+use crate::models::instance::{DatabaseType, Instance, PRIMARY_PORT};
+use crate::state::StateManager;
 
 /// Base ports for each database type
 pub const BASE_PORT_POSTGRES: u16 = 5432;
 pub const BASE_PORT_REDIS: u16 = 6379;
+pub const BASE_PORT_VALKEY: u16 = 6379;
+pub const BASE_PORT_KEYDB: u16 = 6379;
 pub const BASE_PORT_MYSQL: u16 = 3306;
+pub const BASE_PORT_MARIADB: u16 = 3306;
 pub const BASE_PORT_MONGODB: u16 = 27017;
+pub const BASE_PORT_CLICKHOUSE: u16 = 8123;
+pub const BASE_PORT_CLICKHOUSE_NATIVE: u16 = 9000;
+pub const BASE_PORT_ELASTICSEARCH: u16 = 9200;
+pub const BASE_PORT_OPENSEARCH: u16 = 9200;
+pub const BASE_PORT_SEARCH_TRANSPORT: u16 = 9300;
+pub const BASE_PORT_SQLSERVER: u16 = 1433;
 
 /// Get the base port for a database type
 pub fn get_base_port(database_type: &DatabaseType) -> u16 {
     match database_type {
         DatabaseType::PostgreSQL => BASE_PORT_POSTGRES,
         DatabaseType::Redis => BASE_PORT_REDIS,
+        DatabaseType::Valkey => BASE_PORT_VALKEY,
+        DatabaseType::KeyDB => BASE_PORT_KEYDB,
         DatabaseType::MySQL => BASE_PORT_MYSQL,
+        DatabaseType::MariaDB => BASE_PORT_MARIADB,
         DatabaseType::MongoDB => BASE_PORT_MONGODB,
+        DatabaseType::ClickHouse => BASE_PORT_CLICKHOUSE,
+        DatabaseType::Elasticsearch => BASE_PORT_ELASTICSEARCH,
+        DatabaseType::OpenSearch => BASE_PORT_OPENSEARCH,
+        DatabaseType::SqlServer => BASE_PORT_SQLSERVER,
     }
 }
 
-/// Get occupied ports from all running Docker containers
-#[tauri::command]
-pub async fn get_occupied_ports() -> Result<Vec<u16>, String> {
-    let docker = Docker::connect_with_local_defaults()
-        .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
+/// Ports each database listens on inside its container
+pub const CONTAINER_PORT_POSTGRES: u16 = 5432;
+pub const CONTAINER_PORT_REDIS: u16 = 6379;
+pub const CONTAINER_PORT_VALKEY: u16 = 6379;
+pub const CONTAINER_PORT_KEYDB: u16 = 6379;
+pub const CONTAINER_PORT_MYSQL: u16 = 3306;
+pub const CONTAINER_PORT_MARIADB: u16 = 3306;
+pub const CONTAINER_PORT_MONGODB: u16 = 27017;
+pub const CONTAINER_PORT_CLICKHOUSE: u16 = 8123;
+pub const CONTAINER_PORT_CLICKHOUSE_NATIVE: u16 = 9000;
+pub const CONTAINER_PORT_ELASTICSEARCH: u16 = 9200;
+pub const CONTAINER_PORT_OPENSEARCH: u16 = 9200;
+pub const CONTAINER_PORT_SEARCH_TRANSPORT: u16 = 9300;
+pub const CONTAINER_PORT_SQLSERVER: u16 = 1433;
+
+/// Get the port a database type listens on inside its container
+///
+/// This is fixed by the image and independent of the host port it is published on.
+pub fn get_container_port(database_type: &DatabaseType) -> u16 {
+    match database_type {
+        DatabaseType::PostgreSQL => CONTAINER_PORT_POSTGRES,
+        DatabaseType::Redis => CONTAINER_PORT_REDIS,
+        DatabaseType::Valkey => CONTAINER_PORT_VALKEY,
+        DatabaseType::KeyDB => CONTAINER_PORT_KEYDB,
+        DatabaseType::MySQL => CONTAINER_PORT_MYSQL,
+        DatabaseType::MariaDB => CONTAINER_PORT_MARIADB,
+        DatabaseType::MongoDB => CONTAINER_PORT_MONGODB,
+        DatabaseType::ClickHouse => CONTAINER_PORT_CLICKHOUSE,
+        DatabaseType::Elasticsearch => CONTAINER_PORT_ELASTICSEARCH,
+        DatabaseType::OpenSearch => CONTAINER_PORT_OPENSEARCH,
+        DatabaseType::SqlServer => CONTAINER_PORT_SQLSERVER,
+    }
+}
+
+/// A port published next to the primary one
+pub struct ExtraPort {
+    pub name: &'static str,
+    pub container_port: u16,
+    pub base_port: u16,
+}
+
+/// Get the additional ports a database type publishes, beyond the primary one
+pub fn get_extra_ports(database_type: &DatabaseType) -> &'static [ExtraPort] {
+    match database_type {
+        // The primary port is HTTP; native-protocol clients use 9000
+        DatabaseType::ClickHouse => &[ExtraPort {
+            name: "native",
+            container_port: CONTAINER_PORT_CLICKHOUSE_NATIVE,
+            base_port: BASE_PORT_CLICKHOUSE_NATIVE,
+        }],
+        // Node-to-node traffic, for clients that join as transport clients
+        DatabaseType::Elasticsearch | DatabaseType::OpenSearch => &[ExtraPort {
+            name: "transport",
+            container_port: CONTAINER_PORT_SEARCH_TRANSPORT,
+            base_port: BASE_PORT_SEARCH_TRANSPORT,
+        }],
+        _ => &[],
+    }
+}
+
+/// Get ports that are taken or spoken for on this host
+///
+/// Covers ports published by running containers, ports of stored instances
+/// (a stopped instance claims its port again when it starts) and, on Linux,
+/// sockets listening on the host. Ports held by other processes elsewhere
+/// are only caught by `is_port_available`, which tries to bind them.
+pub async fn occupied_ports(docker: &Docker) -> Result<Vec<u16>, LdbError> {
+    let mut occupied_ports = published_ports(docker).await?;
+    occupied_ports.extend(
+        StateManager::new()?
+            .load_instances()?
+            .iter()
+            .flat_map(|i| i.ports().into_values()),
+    );
+    occupied_ports.extend(host_listeners());
+
+    occupied_ports.sort();
+    occupied_ports.dedup();
+    Ok(occupied_ports)
+}
 
+/// Ports published by running containers
+async fn published_ports(docker: &Docker) -> Result<Vec<u16>, LdbError> {
     let options = Some(ListContainersOptions::<String> {
         all: false, // Only running containers
         ..Default::default()
@@ -33,55 +134,302 @@ pub async fn get_occupied_ports() -> Result<Vec<u16>, String> {
     let containers = docker
         .list_containers(options)
         .await
-        .map_err(|e| format!("Failed to list containers: {}", e))?;
+        .map_err(|e| LdbError::docker("Failed to list containers", e))?;
 
-    let mut occupied_ports: Vec<u16> = Vec::new();
+    Ok(containers
+        .into_iter()
+        .flat_map(|container| container.ports.unwrap_or_default())
+        .filter_map(|port| port.public_port)
+        .collect())
+}
 
-    for container in containers {
-        if let Some(ports) = container.ports {
-            for port in ports {
-                if let Some(public_port) = port.public_port {
-                    if !occupied_ports.contains(&public_port) {
-                        occupied_ports.push(public_port);
+/// Ports with a TCP socket listening on the host, from /proc/net/tcp{,6}
+///
+/// Under rootless engines and Docker Desktop the published ports of
+/// containers show up here too, held by the port forwarder.
+#[cfg(target_os = "linux")]
+fn host_listeners() -> Vec<u16> {
+    const TCP_LISTEN: &str = "0A";
+
+    ["/proc/net/tcp", "/proc/net/tcp6"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .flat_map(|table| {
+            table
+                .lines()
+                .skip(1) // header
+                .filter_map(|line| {
+                    // sl local_address rem_address st ..., addresses as HEXIP:HEXPORT
+                    let mut fields = line.split_whitespace().skip(1);
+                    let local = fields.next()?;
+                    let state = fields.nth(1)?;
+                    if state != TCP_LISTEN {
+                        return None;
                     }
-                }
-            }
+                    let (_, port) = local.rsplit_once(':')?;
+                    u16::from_str_radix(port, 16).ok()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn host_listeners() -> Vec<u16> {
+    Vec::new()
+}
+
+/// Whether a port can be bound on the host right now
+///
+/// Tries both the wildcard and the loopback address: on some platforms a
+/// wildcard bind succeeds even though another process listens on loopback.
+fn can_bind(port: u16) -> bool {
+    [Ipv4Addr::UNSPECIFIED, Ipv4Addr::LOCALHOST]
+        .iter()
+        .all(|ip| TcpListener::bind((*ip, port)).is_ok())
+}
+
+/// Check if a port is available: not occupied and bindable on the host
+pub async fn is_port_available(docker: &Docker, port: u16) -> Result<bool, LdbError> {
+    let occupied = occupied_ports(docker).await?;
+    Ok(!occupied.contains(&port) && can_bind(port))
+}
+
+/// Ports an instance of a database type may be given, inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+/// Setting holding the allowed port range of a database type
+fn port_range_setting(database_type: &DatabaseType) -> String {
+    let name = serde_json::to_value(database_type)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+
+    format!("ports.range.{}", name)
+}
+
+/// Get the allowed port range of a database type
+///
+/// Defaults to everything from the type's base port up.
+pub fn port_range(database_type: &DatabaseType) -> Result<PortRange, LdbError> {
+    let configured = StateManager::new()?
+        .get_setting(&port_range_setting(database_type))?
+        .and_then(|value| serde_json::from_str(&value).ok());
+
+    Ok(configured.unwrap_or(PortRange {
+        start: get_base_port(database_type),
+        end: u16::MAX,
+    }))
+}
+
+/// Ports taken on the host, gathered once and then checked against locally
+///
+/// Ports handed out by `take` count as occupied for the rest of the search,
+/// so a batch never gets the same port twice.
+struct PortSearch {
+    occupied: HashSet<u16>,
+}
+
+impl PortSearch {
+    async fn new(docker: &Docker) -> Result<Self, LdbError> {
+        Ok(Self {
+            occupied: occupied_ports(docker).await?.into_iter().collect(),
+        })
+    }
+
+    /// Claim the first free port in a range
+    fn take(&mut self, range: PortRange) -> Result<u16, LdbError> {
+        let port = (range.start..=range.end)
+            .find(|port| !self.occupied.contains(port) && can_bind(*port))
+            .ok_or(LdbError::NoPortAvailable { start: range.start })?;
+
+        self.occupied.insert(port);
+        Ok(port)
+    }
+
+    /// Claim a specific port, failing if it is taken
+    fn claim(&mut self, port: u16) -> Result<u16, LdbError> {
+        if self.occupied.contains(&port) || !can_bind(port) {
+            return Err(LdbError::PortInUse { port });
         }
+
+        self.occupied.insert(port);
+        Ok(port)
     }
+}
 
-    occupied_ports.sort();
-    Ok(occupied_ports)
+/// Find an available port, starting from the preferred port
+pub async fn available_port(docker: &Docker, preferred_port: Option<u16>) -> Result<u16, LdbError> {
+    let start = preferred_port.unwrap_or(BASE_PORT_POSTGRES);
+
+    PortSearch::new(docker).await?.take(PortRange {
+        start,
+        end: u16::MAX,
+    })
 }
 
-/// Check if a port is available (not occupied by any container)
-async fn is_port_available(port: u16) -> Result<bool, String> {
-    let occupied = get_occupied_ports().await?;
-    Ok(!occupied.contains(&port))
+/// Find the next available port for a specific database type, within its allowed range
+pub async fn next_port_for_type(docker: &Docker, database_type: &DatabaseType) -> Result<u16, LdbError> {
+    let range = port_range(database_type)?;
+    PortSearch::new(docker).await?.take(range)
 }
 
-/// Find an available port, starting from the preferred port
-#[tauri::command]
-pub async fn get_available_port(preferred_port: Option<u16>) -> Result<u16, String> {
-    let start_port = preferred_port.unwrap_or(BASE_PORT_POSTGRES);
+/// Claim an instance's host ports by name, the main port under "primary"
+///
+/// Ports in `requested` are used as given if free; the rest are searched
+/// for from the type's range or base ports.
+fn take_instance_ports(
+    search: &mut PortSearch,
+    database_type: &DatabaseType,
+    requested: &BTreeMap<String, u16>,
+) -> Result<BTreeMap<String, u16>, LdbError> {
+    let extra = get_extra_ports(database_type);
+    if let Some(name) = requested
+        .keys()
+        .find(|name| *name != PRIMARY_PORT && !extra.iter().any(|p| p.name == name.as_str()))
+    {
+        return Err(LdbError::invalid_input(format!(
+            "{:?} instances have no port named \"{}\"",
+            database_type, name
+        )));
+    }
+
+    let mut ports = BTreeMap::new();
+    let primary = match requested.get(PRIMARY_PORT) {
+        Some(&port) => search.claim(port)?,
+        None => search.take(port_range(database_type)?)?,
+    };
+    ports.insert(PRIMARY_PORT.to_string(), primary);
 
-    // Check if preferred port is available
-    if is_port_available(start_port).await? {
-        return Ok(start_port);
+    for p in extra {
+        let port = match requested.get(p.name) {
+            Some(&port) => search.claim(port)?,
+            None => search.take(PortRange {
+                start: p.base_port,
+                end: u16::MAX,
+            })?,
+        };
+        ports.insert(p.name.to_string(), port);
     }
 
-    // Search for next available port
-    for port in start_port..=65535 {
-        if is_port_available(port).await? {
-            return Ok(port);
+    Ok(ports)
+}
+
+/// Pick every host port a new instance needs, honouring requested ones
+pub async fn allocate_named_ports(
+    docker: &Docker,
+    database_type: &DatabaseType,
+    requested: &BTreeMap<String, u16>,
+) -> Result<BTreeMap<String, u16>, LdbError> {
+    let mut search = PortSearch::new(docker).await?;
+    take_instance_ports(&mut search, database_type, requested)
+}
+
+/// Find new host ports for those of a stored instance that are taken on the host
+///
+/// Unlike `is_port_available`, the reservations of stored instances do not
+/// count; only running containers and other processes on the host do.
+/// Returns replacements by name, only for the ports that have to move.
+pub async fn conflicting_ports(docker: &Docker, instance: &Instance) -> Result<BTreeMap<String, u16>, LdbError> {
+    let published = published_ports(docker).await?;
+    let listeners = host_listeners();
+    let mut search = PortSearch::new(docker).await?;
+    let mut moved = BTreeMap::new();
+
+    for (name, port) in instance.ports() {
+        if !published.contains(&port) && !listeners.contains(&port) && can_bind(port) {
+            continue;
         }
+
+        let range = match get_extra_ports(&instance.database_type).iter().find(|p| p.name == name) {
+            Some(p) => PortRange {
+                start: p.base_port,
+                end: u16::MAX,
+            },
+            None => port_range(&instance.database_type)?,
+        };
+        moved.insert(name, search.take(range)?);
     }
 
-    Err("No available ports found".to_string())
+    Ok(moved)
+}
+
+/// Find distinct available ports for several instances at once, in order
+pub async fn allocate_ports(
+    docker: &Docker,
+    database_types: &[DatabaseType],
+) -> Result<Vec<BTreeMap<String, u16>>, LdbError> {
+    let mut search = PortSearch::new(docker).await?;
+
+    database_types
+        .iter()
+        .map(|database_type| take_instance_ports(&mut search, database_type, &BTreeMap::new()))
+        .collect()
+}
+
+/// Get ports that are taken or spoken for on this host
+#[tauri::command]
+pub async fn get_occupied_ports(client: State<'_, DockerClient>) -> Result<Vec<u16>, LdbError> {
+    let docker = client.connection().await?;
+    occupied_ports(&docker).await
+}
+
+/// Find an available port, starting from the preferred port
+#[tauri::command]
+pub async fn get_available_port(
+    client: State<'_, DockerClient>,
+    preferred_port: Option<u16>,
+) -> Result<u16, LdbError> {
+    let docker = client.connection().await?;
+    available_port(&docker, preferred_port).await
 }
 
 /// Get the next available port for a specific database type
 #[tauri::command]
-pub async fn get_next_port_for_type(database_type: DatabaseType) -> Result<u16, String> {
-    let base_port = get_base_port(&database_type);
-    get_available_port(Some(base_port)).await
+pub async fn get_next_port_for_type(
+    client: State<'_, DockerClient>,
+    database_type: DatabaseType,
+) -> Result<u16, LdbError> {
+    let docker = client.connection().await?;
+    next_port_for_type(&docker, &database_type).await
+}
+
+/// Find distinct available ports for several instances, one port map per database type given
+#[tauri::command]
+pub async fn allocate_instance_ports(
+    client: State<'_, DockerClient>,
+    database_types: Vec<DatabaseType>,
+) -> Result<Vec<BTreeMap<String, u16>>, LdbError> {
+    let docker = client.connection().await?;
+    allocate_ports(&docker, &database_types).await
+}
+
+/// Get the allowed port range of a database type
+#[tauri::command]
+pub async fn get_port_range(database_type: DatabaseType) -> Result<PortRange, LdbError> {
+    port_range(&database_type)
+}
+
+/// Set the allowed port range of a database type, or `None` to reset it to the default
+#[tauri::command]
+pub async fn set_port_range(database_type: DatabaseType, range: Option<PortRange>) -> Result<PortRange, LdbError> {
+    let value = match range {
+        Some(range) if range.start == 0 || range.start > range.end => {
+            return Err(LdbError::invalid_input(format!(
+                "Invalid port range {}-{}",
+                range.start, range.end
+            )));
+        }
+        Some(range) => Some(
+            serde_json::to_string(&range).map_err(|e| LdbError::internal("Failed to serialize port range", e))?,
+        ),
+        None => None,
+    };
+
+    StateManager::new()?.set_setting(&port_range_setting(&database_type), value.as_deref())?;
+    port_range(&database_type)
 }
@@ -0,0 +1,508 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use bollard::container::{
+    Config, CreateContainerOptions, ListContainersOptions, NetworkingConfig, RemoveContainerOptions,
+    RenameContainerOptions, StartContainerOptions,
+};
+use bollard::models::{ContainerInspectResponse, EndpointSettings, MountPoint, MountPointTypeEnum};
+use bollard::Docker;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::commands::instances::{self, password_from_env};
+use crate::commands::volumes;
+use crate::docker::engine;
+use crate::docker::labels;
+use crate::docker::DockerClient;
+use crate::error::LdbError;
+use crate::models::instance::{DatabaseType, Instance, InstanceStatus};
+use crate::secrets;
+use crate::state::StateManager;
+
+/// A container that looks like ours but has no stored instance
+#[derive(Clone, Serialize)]
+pub struct UnknownContainer {
+    pub container_id: String,
+    pub container_name: String,
+    pub image: String,
+    /// Docker state, e.g. "running" or "exited"
+    pub state: String,
+    /// Instance ID from the container's labels; `None` for legacy containers
+    pub instance_id: Option<Uuid>,
+}
+
+/// A directory under the volumes directory that no instance uses
+#[derive(Clone, Serialize)]
+pub struct OrphanedVolume {
+    /// Directory name, the ID of the instance that once owned it
+    pub instance_id: String,
+    pub path: String,
+}
+
+/// Differences between stored state and what Docker and the disk hold
+#[derive(Clone, Serialize)]
+pub struct ReconciliationReport {
+    /// Stored instances whose container was removed outside the app
+    pub missing_containers: Vec<Instance>,
+    /// Containers carrying our labels or legacy name with no stored instance
+    pub unknown_containers: Vec<UnknownContainer>,
+    /// Volume directories left behind by deleted instances
+    pub orphaned_volumes: Vec<OrphanedVolume>,
+}
+
+/// Which kind of discrepancy an action applies to
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    MissingContainer,
+    UnknownContainer,
+    OrphanedVolume,
+}
+
+/// What to do about a discrepancy
+///
+/// - `Recreate`: build a new container for a stored instance, reusing its volume
+/// - `Adopt`: store an unknown container as an instance
+/// - `Purge`: forget the instance, remove the container, or delete the volume
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconcileAction {
+    Recreate,
+    Adopt,
+    Purge,
+}
+
+/// Compare stored instances with Docker containers and the volumes directory
+#[tauri::command]
+pub async fn reconcile_instances(client: State<'_, DockerClient>) -> Result<ReconciliationReport, LdbError> {
+    let docker = client.connection().await?;
+    build_report(&docker).await
+}
+
+async fn build_report(docker: &Docker) -> Result<ReconciliationReport, LdbError> {
+    let stored = StateManager::new()?.load_instances()?;
+
+    let containers = docker
+        .list_containers(Some(ListContainersOptions::<String> {
+            all: true,
+            ..Default::default()
+        }))
+        .await
+        .map_err(|e| LdbError::docker("Failed to list containers", e))?;
+
+    let mut found = HashSet::new();
+    let mut unknown_containers = Vec::new();
+
+    for container in containers {
+        let name = container
+            .names
+            .as_ref()
+            .and_then(|names| names.first())
+            .map(|n| n.trim_start_matches('/').to_string())
+            .unwrap_or_default();
+
+        if let Some(instance) = instances::find_stored_instance(container.labels.as_ref(), &name, &stored) {
+            found.insert(instance.id);
+            continue;
+        }
+
+        let instance_id = labels::instance_id(container.labels.as_ref());
+        let legacy = !labels::is_labeled(container.labels.as_ref()) && name.starts_with("ldb-");
+        if instance_id.is_none() && !legacy {
+            continue;
+        }
+
+        unknown_containers.push(UnknownContainer {
+            container_id: container.id.unwrap_or_default(),
+            container_name: name,
+            image: container.image.unwrap_or_default(),
+            state: container.state.unwrap_or_default(),
+            instance_id,
+        });
+    }
+
+    let missing_containers = stored
+        .iter()
+        .filter(|i| !found.contains(&i.id))
+        .cloned()
+        .collect();
+
+    // A volume is in use if a stored instance or an unknown container owns it
+    let owners: HashSet<String> = stored
+        .iter()
+        .map(|i| i.id.to_string())
+        .chain(unknown_containers.iter().filter_map(|c| c.instance_id.map(|id| id.to_string())))
+        .collect();
+    let in_use: HashSet<&str> = stored.iter().filter_map(|i| i.volume_path.as_deref()).collect();
+
+    let mut orphaned_volumes = Vec::new();
+    let volume_dir = StateManager::get_volume_dir()?;
+    if let Ok(entries) = fs::read_dir(&volume_dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let instance_id = entry.file_name().to_string_lossy().to_string();
+            let path_str = path.to_string_lossy().to_string();
+
+            if !path.is_dir() || owners.contains(&instance_id) || in_use.contains(path_str.as_str()) {
+                continue;
+            }
+
+            orphaned_volumes.push(OrphanedVolume {
+                instance_id,
+                path: path_str,
+            });
+        }
+    }
+
+    Ok(ReconciliationReport {
+        missing_containers,
+        unknown_containers,
+        orphaned_volumes,
+    })
+}
+
+/// Apply an action to one entry of the reconciliation report
+///
+/// `id` is the instance ID for missing containers and orphaned volumes, and
+/// the container ID for unknown containers. Returns the affected instance
+/// for `Recreate` and `Adopt`.
+#[tauri::command]
+pub async fn resolve_discrepancy(
+    client: State<'_, DockerClient>,
+    kind: DiscrepancyKind,
+    id: String,
+    action: ReconcileAction,
+) -> Result<Option<Instance>, LdbError> {
+    let docker = client.connection().await?;
+    let state_manager = StateManager::new()?;
+
+    match (kind, action) {
+        (DiscrepancyKind::MissingContainer, ReconcileAction::Recreate) => {
+            let instance = stored_instance(&state_manager, &id)?;
+            recreate_missing(&docker, &state_manager, instance).await.map(Some)
+        }
+        (DiscrepancyKind::MissingContainer, ReconcileAction::Purge) => {
+            let instance = stored_instance(&state_manager, &id)?;
+            state_manager.remove_instance(&id)?;
+            if let Some(secret_ref) = &instance.password_ref {
+                secrets::delete(secret_ref)?;
+            }
+            // The volume stays; it shows up as orphaned and can be purged on its own
+            Ok(None)
+        }
+        (DiscrepancyKind::UnknownContainer, ReconcileAction::Adopt) => {
+            adopt(&docker, &state_manager, &id, None).await.map(Some)
+        }
+        (DiscrepancyKind::UnknownContainer, ReconcileAction::Purge) => {
+            // Only containers carrying our labels or legacy name, and not
+            // backing a stored instance, may be removed
+            let report = build_report(&docker).await?;
+            if !report.unknown_containers.iter().any(|c| c.container_id == id) {
+                return Err(LdbError::invalid_input(format!(
+                    "Container {} is not an unknown ldb container",
+                    id
+                )));
+            }
+
+            docker
+                .remove_container(
+                    &id,
+                    Some(RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .map_err(|e| LdbError::docker("Failed to remove container", e))?;
+            Ok(None)
+        }
+        (DiscrepancyKind::OrphanedVolume, ReconcileAction::Purge) => {
+            // Volume directories are named after instance IDs, directly
+            // inside the volumes directory
+            if Uuid::parse_str(&id).is_err() {
+                return Err(LdbError::invalid_input(format!("Invalid volume: {}", id)));
+            }
+
+            let report = build_report(&docker).await?;
+            let Some(orphan) = report.orphaned_volumes.iter().find(|v| v.instance_id == id) else {
+                return Err(LdbError::invalid_input(format!(
+                    "Volume of instance {} is not orphaned",
+                    id
+                )));
+            };
+            let path = Path::new(&orphan.path);
+
+            let engine = engine::detect(&docker).await?;
+            engine::remove_volume_dir(path, &engine)?;
+            Ok(None)
+        }
+        (kind, action) => Err(LdbError::invalid_input(format!(
+            "Cannot {:?} a {:?}",
+            action, kind
+        ))),
+    }
+}
+
+fn stored_instance(state_manager: &StateManager, id: &str) -> Result<Instance, LdbError> {
+    state_manager
+        .get_instance(id)?
+        .ok_or_else(|| LdbError::InstanceNotFound { id: id.to_string() })
+}
+
+/// Build a fresh container for a stored instance, keeping its data volume
+async fn recreate_missing(
+    docker: &Docker,
+    state_manager: &StateManager,
+    instance: Instance,
+) -> Result<Instance, LdbError> {
+    // Adopted instances may keep their data in a named volume instead
+    if let Some(volume_path) = instance.volume_path.as_ref().filter(|p| Path::new(p).is_absolute()) {
+        fs::create_dir_all(volume_path)
+            .map_err(|e| LdbError::state("Failed to create instance volume directory", e))?;
+    }
+
+    let password = secrets::resolve(instance.password_ref.as_ref())?;
+    let container_name = instances::create_container(docker, &instance, &password).await?;
+
+    let recreated = Instance {
+        status: InstanceStatus::Stopped,
+        container_name: Some(container_name),
+        ..instance
+    };
+    state_manager.update_instance(recreated.clone())?;
+
+    Ok(recreated)
+}
+
+/// Take over an existing database container, e.g. one started by hand or by compose
+///
+/// The database type, image, port, data mount and credentials are read from
+/// the container. Docker cannot add labels to an existing container, so an
+/// unlabeled one is recreated from its own configuration plus our labels;
+/// its data stays on the same volume or bind mount.
+#[tauri::command]
+pub async fn adopt_container(
+    client: State<'_, DockerClient>,
+    container_id: String,
+    name: Option<String>,
+) -> Result<Instance, LdbError> {
+    let docker = client.connection().await?;
+    let state_manager = StateManager::new()?;
+
+    adopt(&docker, &state_manager, &container_id, name).await
+}
+
+async fn adopt(
+    docker: &Docker,
+    state_manager: &StateManager,
+    container_id: &str,
+    name: Option<String>,
+) -> Result<Instance, LdbError> {
+    let info = docker
+        .inspect_container(container_id, None)
+        .await
+        .map_err(|e| LdbError::docker("Failed to inspect container", e))?;
+
+    let config = info.config.clone().unwrap_or_default();
+    let labeled_id = labels::instance_id(config.labels.as_ref());
+    if let Some(id) = labeled_id {
+        if state_manager.get_instance(&id.to_string())?.is_some() {
+            return Err(LdbError::invalid_input(format!(
+                "Container {} is already managed",
+                container_id
+            )));
+        }
+    }
+
+    // Keep the labeled ID so the container resolves to the new record
+    let id = labeled_id.unwrap_or_else(Uuid::new_v4);
+    let mut instance = instances::reconcile_instance(id, None, info.clone())?;
+    if let Some(name) = name.filter(|n| !n.trim().is_empty()) {
+        instance.name = name;
+    }
+
+    if instance.port == 0 {
+        return Err(LdbError::invalid_input(format!(
+            "Container {} does not publish the {:?} port on the host",
+            container_id, instance.database_type
+        )));
+    }
+
+    let data_path = volumes::get_data_path(&instance.database_type, &instance.tag);
+    instance.volume_path = data_mount(info.mounts.as_deref().unwrap_or_default(), data_path);
+    // A labeled container may still use the directory the app made for it
+    let own_dir = StateManager::get_volume_dir()?.join(id.to_string());
+    instance.adopted_mount = instance.volume_path.as_deref().is_some_and(|p| Path::new(p) != own_dir);
+
+    let password = adopted_password(
+        &instance.database_type,
+        config.env.as_deref().unwrap_or_default(),
+        config.cmd.as_deref().unwrap_or_default(),
+    );
+
+    if labeled_id.is_none() {
+        if instance.volume_path.is_none() {
+            return Err(LdbError::invalid_input(format!(
+                "Container {} has no volume at {}; its data would be lost when relabeling it",
+                container_id, data_path
+            )));
+        }
+
+        instance.container_name = Some(replace_container(docker, &instance, info).await?);
+    }
+
+    if !password.is_empty() {
+        let key = secrets::instance_password_key(&instance.id.to_string());
+        instance.password_ref = Some(secrets::store(&key, &password)?);
+    }
+
+    state_manager.add_instance(instance.clone())?;
+    Ok(instance)
+}
+
+/// Where a container keeps the data at `data_path`: a host directory or a volume name
+fn data_mount(mounts: &[MountPoint], data_path: &str) -> Option<String> {
+    let mount = mounts
+        .iter()
+        .find(|m| m.destination.as_deref().is_some_and(|d| Path::new(d) == Path::new(data_path)))?;
+
+    match mount.typ {
+        Some(MountPointTypeEnum::VOLUME) => mount.name.clone(),
+        _ => mount.source.clone(),
+    }
+}
+
+/// Root password of a container we did not create
+///
+/// Redis-compatible images take it on the command line rather than from the environment.
+fn adopted_password(database_type: &DatabaseType, env: &[String], cmd: &[String]) -> String {
+    if database_type.is_redis_compatible() {
+        if let Some(password) = cmd
+            .iter()
+            .position(|arg| arg == "--requirepass")
+            .and_then(|i| cmd.get(i + 1))
+        {
+            return password.clone();
+        }
+    }
+
+    password_from_env(database_type, env)
+}
+
+/// Replace a container with a copy of itself that carries our labels and the instance's ports
+///
+/// Everything else (environment, command, mounts, networks) is taken from
+/// the inspected container, so this is safe for containers we did not
+/// build. The old container is renamed out of the way first, so it can be
+/// put back if the new one cannot be created. Returns the container name.
+pub async fn replace_container(
+    docker: &Docker,
+    instance: &Instance,
+    info: ContainerInspectResponse,
+) -> Result<String, LdbError> {
+    let old_id = info.id.clone().unwrap_or_default();
+    let container_name = info.name.clone().unwrap_or_default().trim_start_matches('/').to_string();
+    let was_running = info.state.as_ref().and_then(|s| s.running) == Some(true);
+
+    let mut config: Config<String> = info.config.unwrap_or_default().into();
+    // Docker defaults the hostname to the container ID; let the copy get its own
+    if config.hostname.as_deref().is_some_and(|h| old_id.starts_with(h)) {
+        config.hostname = None;
+    }
+    config
+        .labels
+        .get_or_insert_with(HashMap::new)
+        .extend(labels::for_instance(&instance.id, &instance.database_type));
+
+    let mut host_config = info.host_config.unwrap_or_default();
+    volumes::carry_volumes(&mut host_config, info.mounts.unwrap_or_default());
+
+    // Move published ports, keeping the host addresses they are bound to.
+    // Older versions published `{host_port}/tcp` inside the container; such
+    // a binding moves to the database's port.
+    let port_bindings = host_config.port_bindings.get_or_insert_with(HashMap::new);
+    for (container_port, host_port) in instances::port_mappings(instance) {
+        let key = format!("{}/tcp", container_port);
+        if !port_bindings.get(&key).is_some_and(Option::is_some) {
+            if let Some(bindings) = port_bindings.remove(&format!("{}/tcp", host_port)) {
+                port_bindings.insert(key.clone(), bindings);
+                config.exposed_ports.get_or_insert_with(HashMap::new).insert(key.clone(), HashMap::new());
+            }
+        }
+
+        if let Some(Some(bindings)) = port_bindings.get_mut(&key) {
+            for binding in bindings {
+                binding.host_port = Some(host_port.to_string());
+            }
+        }
+    }
+    config.host_config = Some(host_config);
+
+    // Rejoin user-defined networks (e.g. a compose project's) under the same aliases
+    let endpoints_config: HashMap<String, EndpointSettings> = info
+        .network_settings
+        .and_then(|ns| ns.networks)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(network, _)| !["bridge", "host", "none"].contains(&network.as_str()))
+        .map(|(network, endpoint)| {
+            let aliases = endpoint
+                .aliases
+                .map(|a| a.into_iter().filter(|alias| !old_id.starts_with(alias.as_str())).collect());
+            (
+                network,
+                EndpointSettings {
+                    aliases,
+                    links: endpoint.links,
+                    ipam_config: endpoint.ipam_config,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect();
+    if !endpoints_config.is_empty() {
+        config.networking_config = Some(NetworkingConfig { endpoints_config });
+    }
+
+    let replaced_name = format!("{}-replaced-{}", container_name, &old_id[..old_id.len().min(12)]);
+    rename(docker, &old_id, &replaced_name).await?;
+
+    let options = CreateContainerOptions {
+        name: container_name.clone(),
+        platform: None,
+    };
+    if let Err(e) = docker.create_container(Some(options), config).await {
+        let _ = rename(docker, &old_id, &container_name).await;
+        return Err(LdbError::docker("Failed to recreate container", e));
+    }
+
+    docker
+        .remove_container(
+            &old_id,
+            Some(RemoveContainerOptions {
+                force: true,
+                ..Default::default()
+            }),
+        )
+        .await
+        .map_err(|e| LdbError::docker("Failed to remove replaced container", e))?;
+
+    if was_running {
+        docker
+            .start_container(&container_name, None::<StartContainerOptions<String>>)
+            .await
+            .map_err(|e| LdbError::docker("Failed to start container", e))?;
+    }
+
+    Ok(container_name)
+}
+
+async fn rename(docker: &Docker, container_id: &str, name: &str) -> Result<(), LdbError> {
+    docker
+        .rename_container(container_id, RenameContainerOptions { name })
+        .await
+        .map_err(|e| LdbError::docker("Failed to rename container", e))
+}
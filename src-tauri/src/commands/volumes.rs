@@ -0,0 +1,286 @@
+use bollard::models::{HostConfig, MountPoint, MountPointTypeEnum};
+use bollard::Docker;
+use serde::Serialize;
+use tauri::State;
+
+use crate::docker::labels;
+use crate::docker::DockerClient;
+use crate::error::LdbError;
+use crate::models::instance::{DatabaseType, Instance};
+use crate::state::StateManager;
+
+/// Data directory of the official images, by engine and major version
+///
+/// Each entry is `(first major version, path)`, newest first; the first
+/// entry whose version is <= the tag's major version wins. Tags without a
+/// version ("latest", "alpine", ...) track the newest release.
+const DATA_PATHS: &[(DatabaseType, &[(u32, &str)])] = &[
+    // Postgres 18 moved PGDATA to /var/lib/postgresql/18/docker and
+    // declares the volume on /var/lib/postgresql
+    (
+        DatabaseType::PostgreSQL,
+        &[(18, "/var/lib/postgresql"), (0, "/var/lib/postgresql/data")],
+    ),
+    (DatabaseType::MySQL, &[(0, "/var/lib/mysql")]),
+    (DatabaseType::MariaDB, &[(0, "/var/lib/mysql")]),
+    (DatabaseType::MongoDB, &[(0, "/data/db")]),
+    (DatabaseType::Redis, &[(0, "/data")]),
+    (DatabaseType::ClickHouse, &[(0, "/var/lib/clickhouse")]),
+    (DatabaseType::Valkey, &[(0, "/data")]),
+    (DatabaseType::KeyDB, &[(0, "/data")]),
+    (DatabaseType::Elasticsearch, &[(0, "/usr/share/elasticsearch/data")]),
+    (DatabaseType::OpenSearch, &[(0, "/usr/share/opensearch/data")]),
+    (DatabaseType::SqlServer, &[(0, "/var/opt/mssql")]),
+];
+
+/// Get the directory a database stores its data in, inside the container
+pub fn get_data_path(database_type: &DatabaseType, tag: &str) -> &'static str {
+    let versions = DATA_PATHS
+        .iter()
+        .find(|(t, _)| t == database_type)
+        .map(|(_, versions)| *versions)
+        .unwrap_or(&[]);
+
+    let major = major_version(tag).unwrap_or(u32::MAX);
+
+    versions
+        .iter()
+        .find(|(since, _)| major >= *since)
+        .map(|(_, path)| *path)
+        .unwrap_or("/data")
+}
+
+/// Parse the major version from a tag like "16", "15.4" or "8-alpine"
+fn major_version(tag: &str) -> Option<u32> {
+    let digits: String = tag.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// Whether `path` is `mount` or lies inside it
+fn is_within(path: &str, mount: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    let mount = mount.trim_end_matches('/');
+
+    path == mount || path.starts_with(&format!("{}/", mount))
+}
+
+/// Mount the Docker volumes of a container being replaced in its successor
+///
+/// Anonymous volumes are not part of a container's config, so a replacement
+/// would get fresh, empty ones while the data stays behind in the old,
+/// detached volume. Each is bound again by name; where a bind targets the
+/// same path, the volume wins, as that is where the data is.
+pub fn carry_volumes(host_config: &mut HostConfig, mounts: Vec<MountPoint>) {
+    for mount in mounts {
+        let (Some(MountPointTypeEnum::VOLUME), Some(name), Some(destination)) =
+            (mount.typ, mount.name, mount.destination)
+        else {
+            continue;
+        };
+
+        // Declared with --mount, so already part of the host config
+        let declared = host_config
+            .mounts
+            .iter()
+            .flatten()
+            .any(|m| m.target.as_deref() == Some(destination.as_str()));
+        if declared {
+            continue;
+        }
+
+        let binds = host_config.binds.get_or_insert_with(Vec::new);
+        binds.retain(|b| b.split(':').nth(1) != Some(destination.as_str()));
+        binds.push(format!("{}:{}", name, destination));
+    }
+}
+
+/// Check that mounting `data_path` will actually persist the image's data
+///
+/// Compares against the image metadata: at least one declared `VOLUME`, and
+/// `PGDATA` when set, must live inside the mount. Images without metadata
+/// are accepted as-is.
+pub async fn validate_data_path(docker: &Docker, image: &str, data_path: &str) -> Result<(), LdbError> {
+    let inspect = docker
+        .inspect_image(image)
+        .await
+        .map_err(|e| match e {
+            bollard::errors::Error::DockerResponseServerError { status_code: 404, .. } => {
+                LdbError::ImageMissing { image: image.to_string() }
+            }
+            e => LdbError::docker(&format!("Failed to inspect image {}", image), e),
+        })?;
+
+    let Some(config) = inspect.config else {
+        return Ok(());
+    };
+
+    let declared: Vec<String> = config
+        .volumes
+        .map(|volumes| volumes.into_keys().collect())
+        .unwrap_or_default();
+
+    if !declared.is_empty() && !declared.iter().any(|v| is_within(v, data_path)) {
+        return Err(LdbError::DataNotPersisted {
+            image: image.to_string(),
+            data_path: data_path.to_string(),
+            expected: declared.join(", "),
+        });
+    }
+
+    let pgdata = config
+        .env
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|e| e.strip_prefix("PGDATA=").map(str::to_string));
+
+    if let Some(pgdata) = pgdata {
+        if !is_within(&pgdata, data_path) {
+            return Err(LdbError::DataNotPersisted {
+                image: image.to_string(),
+                data_path: data_path.to_string(),
+                expected: pgdata,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// An instance whose data lives in the container layer instead of its volume
+#[derive(Clone, Serialize)]
+pub struct PersistenceWarning {
+    pub instance_id: String,
+    pub name: String,
+    /// Where the instance volume is mounted today, if anywhere
+    pub mounted_at: Option<String>,
+    /// Where the image actually writes its data
+    pub expected: String,
+    pub message: String,
+}
+
+/// Check an instance's container mounts against the data path table
+async fn check_instance(docker: &Docker, instance: &Instance) -> Result<Option<PersistenceWarning>, LdbError> {
+    let Some(container) = labels::find_container(docker, instance).await? else {
+        return Ok(None);
+    };
+
+    let info = docker
+        .inspect_container(&container.id.unwrap_or_default(), None)
+        .await
+        .map_err(|e| LdbError::docker("Failed to inspect container", e))?;
+
+    let expected = get_data_path(&instance.database_type, &instance.tag);
+
+    // The instance volume is the bind mount whose source is the volume
+    // directory, or the named volume of an adopted container
+    let volume_source = instance.volume_path.clone().unwrap_or_default();
+    let mounted_at = info
+        .mounts
+        .unwrap_or_default()
+        .into_iter()
+        .find(|m| {
+            m.source.as_deref() == Some(volume_source.as_str())
+                || m.name.as_deref() == Some(volume_source.as_str())
+        })
+        .and_then(|m| m.destination);
+
+    if mounted_at.as_deref().is_some_and(|m| is_within(expected, m)) {
+        return Ok(None);
+    }
+
+    let message = match &mounted_at {
+        Some(mount) => format!(
+            "Data is not persisted: the volume is mounted at {} but {} writes to {}. \
+             Data will be lost when the container is deleted.",
+            mount, instance.image, expected
+        ),
+        None => format!(
+            "Data is not persisted: no volume is mounted at {}. \
+             Data will be lost when the container is deleted.",
+            expected
+        ),
+    };
+
+    Ok(Some(PersistenceWarning {
+        instance_id: instance.id.to_string(),
+        name: instance.name.clone(),
+        mounted_at,
+        expected: expected.to_string(),
+        message,
+    }))
+}
+
+/// Report instances whose volume is not mounted where their database writes data
+#[tauri::command]
+pub async fn check_data_persistence(
+    client: State<'_, DockerClient>,
+) -> Result<Vec<PersistenceWarning>, LdbError> {
+    let docker = client.connection().await?;
+
+    let state_manager = StateManager::new()?;
+    let mut warnings = Vec::new();
+
+    for instance in state_manager.load_instances()? {
+        if let Some(warning) = check_instance(&docker, &instance).await? {
+            warnings.push(warning);
+        }
+    }
+
+    Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume(name: &str, destination: &str) -> MountPoint {
+        MountPoint {
+            typ: Some(MountPointTypeEnum::VOLUME),
+            name: Some(name.to_string()),
+            destination: Some(destination.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn get_data_path_follows_major_version() {
+        assert_eq!(get_data_path(&DatabaseType::PostgreSQL, "16-alpine"), "/var/lib/postgresql/data");
+        assert_eq!(get_data_path(&DatabaseType::PostgreSQL, "18"), "/var/lib/postgresql");
+        assert_eq!(get_data_path(&DatabaseType::PostgreSQL, "latest"), "/var/lib/postgresql");
+        assert_eq!(get_data_path(&DatabaseType::MongoDB, "7.0"), "/data/db");
+        assert_eq!(get_data_path(&DatabaseType::SqlServer, "2022-latest"), "/var/opt/mssql");
+    }
+
+    #[test]
+    fn carry_volumes_prefers_volume_over_bind_at_same_path() {
+        let mut host_config = HostConfig {
+            binds: Some(vec!["/home/u/.ldb-engine/volumes/x:/var/lib/postgresql/data".to_string()]),
+            ..Default::default()
+        };
+
+        carry_volumes(&mut host_config, vec![volume("abc123", "/var/lib/postgresql/data")]);
+
+        assert_eq!(host_config.binds, Some(vec!["abc123:/var/lib/postgresql/data".to_string()]));
+    }
+
+    #[test]
+    fn carry_volumes_adds_volumes_and_ignores_binds() {
+        let bind = MountPoint {
+            typ: Some(MountPointTypeEnum::BIND),
+            source: Some("/srv/x".to_string()),
+            destination: Some("/data".to_string()),
+            ..Default::default()
+        };
+        let mut host_config = HostConfig {
+            binds: Some(vec!["/srv/x:/data".to_string()]),
+            ..Default::default()
+        };
+
+        carry_volumes(&mut host_config, vec![bind, volume("def456", "/data/db")]);
+
+        assert_eq!(
+            host_config.binds,
+            Some(vec!["/srv/x:/data".to_string(), "def456:/data/db".to_string()])
+        );
+    }
+}
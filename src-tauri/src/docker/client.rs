@@ -1,10 +1,62 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
 use bollard::Docker;
 use bollard::image::CreateImageOptions;
 use futures::StreamExt;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Mutex;
+
+use crate::docker::engine::{self, EngineInfo, Endpoint};
+use crate::error::LdbError;
 
+/// How long a successful health check is trusted before pinging again
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Upper bound on a single ping or version request to the daemon
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Shared connection to the Docker daemon, managed as Tauri state
+///
+/// The connection is opened lazily to the engine picked in settings, or the
+/// first reachable one found by `engine::discover`, with the API version
+/// negotiated against the daemon. It is health-checked with a ping at most every
+/// `HEALTH_CHECK_INTERVAL`; when the ping fails (e.g. the daemon was
+/// restarted) the connection is rebuilt.
 pub struct DockerClient {
-    docker: Docker,
+    connection: Mutex<Connection>,
+}
+
+#[derive(Default)]
+struct Connection {
+    docker: Option<Docker>,
+    status: DockerStatus,
+    checked_at: Option<Instant>,
+}
+
+/// Docker daemon availability, as reported to the frontend
+#[derive(Clone, Default, serde::Serialize)]
+pub struct DockerStatus {
+    pub available: bool,
+    /// Address of the connected engine
+    pub host: Option<String>,
+    pub engine: Option<EngineInfo>,
+    /// Docker Engine version, e.g. "27.3.1"
+    pub version: Option<String>,
+    /// API version negotiated with the daemon
+    pub api_version: Option<String>,
+    pub os: Option<String>,
+    pub arch: Option<String>,
+    /// Why the daemon is unavailable
+    pub error: Option<String>,
+}
+
+impl DockerStatus {
+    fn unavailable(error: &LdbError) -> Self {
+        Self {
+            error: Some(error.to_string()),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Clone, serde::Serialize)]
@@ -22,21 +74,52 @@ pub struct ProgressDetail {
 }
 
 impl DockerClient {
-    pub fn new() -> Result<Self, String> {
-        let docker = Docker::connect_with_local_defaults()
-            .map_err(|e| format!("Failed to connect to Docker: {}", e))?;
+    pub fn new() -> Self {
+        Self {
+            connection: Mutex::new(Connection::default()),
+        }
+    }
+
+    /// Get a healthy connection, reconnecting if the daemon went away
+    pub async fn connection(&self) -> Result<Docker, LdbError> {
+        let mut connection = self.connection.lock().await;
+
+        let fresh = connection
+            .checked_at
+            .is_some_and(|checked| checked.elapsed() < HEALTH_CHECK_INTERVAL);
+        if !fresh || connection.docker.is_none() {
+            connection.refresh().await;
+        }
+
+        match &connection.docker {
+            Some(docker) => Ok(docker.clone()),
+            None => Err(LdbError::DockerUnavailable {
+                message: connection.status.error.clone().unwrap_or_default(),
+            }),
+        }
+    }
 
-        Ok(Self { docker })
+    /// Drop the connection so the next use reconnects, e.g. after the engine setting changed
+    pub async fn reset(&self) {
+        *self.connection.lock().await = Connection::default();
+    }
+
+    /// Health-check the daemon now and report its status
+    pub async fn status(&self) -> DockerStatus {
+        let mut connection = self.connection.lock().await;
+        connection.refresh().await;
+        connection.status.clone()
     }
 
     /// Pull an image from Docker Hub with progress streaming
-    pub async fn pull_image(&self, app: AppHandle, image: &str) -> Result<(), String> {
+    pub async fn pull_image(&self, app: AppHandle, image: &str) -> Result<(), LdbError> {
         let options = CreateImageOptions {
             from_image: image.to_string(),
             ..Default::default()
         };
 
-        let mut stream = self.docker.create_image(Some(options), None, None);
+        let docker = self.connection().await?;
+        let mut stream = docker.create_image(Some(options), None, None);
 
         while let Some(result) = stream.next().await {
             match result {
@@ -58,7 +141,7 @@ impl DockerClient {
                 }
                 Err(e) => {
                     let _ = app.emit("pull-error", format!("Pull failed: {}", e));
-                    return Err(format!("Pull failed: {}", e));
+                    return Err(LdbError::docker("Pull failed", e));
                 }
             }
         }
@@ -70,6 +153,103 @@ impl DockerClient {
 
 impl Default for DockerClient {
     fn default() -> Self {
-        Self::new().expect("Failed to create Docker client")
+        Self::new()
     }
 }
+
+impl Connection {
+    /// Ping the current connection, reconnecting when the ping fails
+    async fn refresh(&mut self) {
+        if let Some(docker) = &self.docker {
+            if with_timeout(docker.ping()).await.is_ok() {
+                self.checked_at = Some(Instant::now());
+                return;
+            }
+        }
+
+        match connect().await {
+            Ok((docker, status)) => {
+                self.docker = Some(docker);
+                self.status = status;
+            }
+            Err(e) => {
+                self.docker = None;
+                self.status = DockerStatus::unavailable(&e);
+            }
+        }
+        self.checked_at = Some(Instant::now());
+    }
+}
+
+/// Connect to the selected engine, or the first reachable discovered one
+async fn connect() -> Result<(Docker, DockerStatus), LdbError> {
+    let endpoints = match engine::selected_host() {
+        Some(host) => vec![Endpoint {
+            name: "Selected engine".to_string(),
+            host,
+        }],
+        None => engine::discover(),
+    };
+
+    let mut last_error =
+        LdbError::docker_unavailable("No Docker or Podman socket found");
+
+    for endpoint in endpoints {
+        match connect_to(&endpoint.host).await {
+            Ok(connected) => return Ok(connected),
+            Err(e) => last_error = e,
+        }
+    }
+
+    Err(last_error)
+}
+
+/// Open a connection to one engine and negotiate the API version with it
+async fn connect_to(host: &str) -> Result<(Docker, DockerStatus), LdbError> {
+    let docker = engine::connect(host)?;
+    let docker = with_timeout(docker.negotiate_version()).await?;
+    let version = with_timeout(docker.version()).await?;
+    let engine = engine::engine_info(&docker, &version).await?;
+
+    let status = DockerStatus {
+        available: true,
+        host: Some(host.to_string()),
+        engine: Some(engine),
+        version: version.version,
+        api_version: Some(docker.client_version().to_string()),
+        os: version.os,
+        arch: version.arch,
+        error: None,
+    };
+
+    Ok((docker, status))
+}
+
+/// Run a daemon request, treating errors and timeouts as "unavailable"
+async fn with_timeout<T>(
+    request: impl Future<Output = Result<T, bollard::errors::Error>>,
+) -> Result<T, LdbError> {
+    tokio::time::timeout(HEALTH_CHECK_TIMEOUT, request)
+        .await
+        .map_err(|_| LdbError::docker_unavailable("Docker daemon did not respond"))?
+        .map_err(LdbError::docker_unavailable)
+}
+
+/// Periodically health-check the daemon, emitting `docker-status-changed`
+/// whenever it becomes available or unavailable
+pub fn spawn_health_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut was_available = None;
+
+        loop {
+            let status = app.state::<DockerClient>().status().await;
+
+            if was_available != Some(status.available) {
+                was_available = Some(status.available);
+                let _ = app.emit("docker-status-changed", status);
+            }
+
+            tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+        }
+    });
+}
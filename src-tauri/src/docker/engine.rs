@@ -0,0 +1,299 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use bollard::system::Version;
+use bollard::{Docker, API_DEFAULT_VERSION};
+use serde::{Deserialize, Serialize};
+
+use crate::error::LdbError;
+use crate::state::StateManager;
+
+/// Setting holding the engine address picked by the user; unset means auto-discovery
+pub const ENGINE_HOST_SETTING: &str = "engine.host";
+
+/// Read/write timeout (seconds) for engine connections, bollard's default
+const CONNECTION_TIMEOUT: u64 = 120;
+
+/// Container engine serving the Docker-compatible API
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EngineKind {
+    #[default]
+    Docker,
+    Podman,
+}
+
+/// What the connected engine is and how it runs containers
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct EngineInfo {
+    pub kind: EngineKind,
+    /// Containers run in a user namespace owned by an unprivileged user
+    pub rootless: bool,
+    /// The engine host enforces SELinux labels on bind mounts
+    pub selinux: bool,
+}
+
+impl EngineInfo {
+    fn from_daemon(version: &Version, security_options: &[String]) -> Self {
+        let podman = version
+            .components
+            .iter()
+            .flatten()
+            .any(|c| c.name.to_lowercase().contains("podman"));
+
+        let has_option = |name: &str| {
+            security_options
+                .iter()
+                .any(|o| o.split(',').any(|part| part == format!("name={}", name)))
+        };
+
+        Self {
+            kind: if podman { EngineKind::Podman } else { EngineKind::Docker },
+            rootless: has_option("rootless"),
+            selinux: has_option("selinux"),
+        }
+    }
+
+    /// Options appended to the instance volume bind, e.g. ":Z,U"
+    ///
+    /// `Z` relabels the directory so an SELinux-confined container may write
+    /// it. `U` makes rootless Podman chown it to the container's database
+    /// user, which is mapped to a subordinate UID on the host.
+    pub fn bind_options(&self) -> String {
+        let mut options = Vec::new();
+
+        if self.selinux {
+            options.push("Z");
+        }
+        if self.kind == EngineKind::Podman && self.rootless {
+            options.push("U");
+        }
+
+        if options.is_empty() {
+            String::new()
+        } else {
+            format!(":{}", options.join(","))
+        }
+    }
+}
+
+/// An address a Docker-compatible engine may be listening on
+#[derive(Clone, Debug, Serialize)]
+pub struct Endpoint {
+    /// Where the endpoint was found, e.g. "DOCKER_HOST" or "Podman (rootless)"
+    pub name: String,
+    /// Engine address, e.g. "unix:///run/user/1000/podman/podman.sock"
+    pub host: String,
+}
+
+impl Endpoint {
+    fn new(name: impl Into<String>, host: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            host: host.into(),
+        }
+    }
+}
+
+/// Find the engines available on this machine, most preferred first
+///
+/// Order: `DOCKER_HOST`, the current Docker context, other Docker
+/// contexts, then well-known Docker and Podman sockets that exist.
+pub fn discover() -> Vec<Endpoint> {
+    let mut endpoints = Vec::new();
+
+    if let Ok(host) = std::env::var("DOCKER_HOST") {
+        if !host.is_empty() {
+            endpoints.push(Endpoint::new("DOCKER_HOST", host));
+        }
+    }
+
+    endpoints.extend(docker_contexts());
+
+    for (name, path) in socket_candidates() {
+        if path.exists() {
+            endpoints.push(Endpoint::new(name, socket_host(&path)));
+        }
+    }
+
+    // Contexts usually point at one of the well-known sockets
+    let mut seen = HashSet::new();
+    endpoints.retain(|e| seen.insert(e.host.clone()));
+    endpoints
+}
+
+/// Endpoints of the Docker CLI contexts in ~/.docker/contexts
+///
+/// The current context (`DOCKER_CONTEXT` or `currentContext` in
+/// ~/.docker/config.json) comes first.
+fn docker_contexts() -> Vec<Endpoint> {
+    let Some(docker_dir) = dirs::home_dir().map(|home| home.join(".docker")) else {
+        return Vec::new();
+    };
+
+    let current = std::env::var("DOCKER_CONTEXT").ok().or_else(|| {
+        let config = fs::read_to_string(docker_dir.join("config.json")).ok()?;
+        let config: serde_json::Value = serde_json::from_str(&config).ok()?;
+        config["currentContext"].as_str().map(str::to_string)
+    });
+
+    // Each context lives in meta/<sha256 of name>/meta.json
+    let Ok(entries) = fs::read_dir(docker_dir.join("contexts").join("meta")) else {
+        return Vec::new();
+    };
+
+    let mut contexts: Vec<(String, String)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let meta = fs::read_to_string(entry.path().join("meta.json")).ok()?;
+            let meta: serde_json::Value = serde_json::from_str(&meta).ok()?;
+            let name = meta["Name"].as_str()?.to_string();
+            let host = meta["Endpoints"]["docker"]["Host"].as_str()?.to_string();
+            Some((name, host))
+        })
+        .collect();
+
+    contexts.sort_by_key(|(name, _)| Some(name) != current.as_ref());
+
+    contexts
+        .into_iter()
+        .map(|(name, host)| Endpoint::new(format!("Docker context \"{}\"", name), host))
+        .collect()
+}
+
+/// Well-known engine sockets, Docker before Podman
+#[cfg(unix)]
+fn socket_candidates() -> Vec<(&'static str, PathBuf)> {
+    let mut candidates = vec![("Docker", PathBuf::from("/var/run/docker.sock"))];
+
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+
+    if let Some(runtime_dir) = &runtime_dir {
+        candidates.push(("Docker (rootless)", runtime_dir.join("docker.sock")));
+    }
+    if let Some(home) = dirs::home_dir() {
+        candidates.push(("Docker Desktop", home.join(".docker/run/docker.sock")));
+    }
+    if let Some(runtime_dir) = &runtime_dir {
+        candidates.push(("Podman (rootless)", runtime_dir.join("podman/podman.sock")));
+    }
+    candidates.push(("Podman", PathBuf::from("/run/podman/podman.sock")));
+
+    candidates
+}
+
+#[cfg(windows)]
+fn socket_candidates() -> Vec<(&'static str, PathBuf)> {
+    vec![
+        ("Docker", PathBuf::from(r"\\.\pipe\docker_engine")),
+        ("Podman", PathBuf::from(r"\\.\pipe\podman-machine-default")),
+    ]
+}
+
+#[cfg(unix)]
+fn socket_host(path: &Path) -> String {
+    format!("unix://{}", path.display())
+}
+
+#[cfg(windows)]
+fn socket_host(path: &Path) -> String {
+    format!("npipe://{}", path.display().to_string().replace('\\', "/"))
+}
+
+/// The engine address picked in settings, if any
+pub fn selected_host() -> Option<String> {
+    StateManager::new()
+        .and_then(|state| state.get_setting(ENGINE_HOST_SETTING))
+        .ok()
+        .flatten()
+}
+
+/// Open a client for an engine address (`unix://`, `npipe://`, `tcp://` or `http://`)
+///
+/// Does not contact the engine; negotiate or ping to check it is reachable.
+pub fn connect(host: &str) -> Result<Docker, LdbError> {
+    let docker = match host {
+        #[cfg(unix)]
+        h if h.starts_with("unix://") => {
+            Docker::connect_with_unix(h, CONNECTION_TIMEOUT, API_DEFAULT_VERSION)
+        }
+        #[cfg(windows)]
+        h if h.starts_with("npipe://") => {
+            Docker::connect_with_named_pipe(h, CONNECTION_TIMEOUT, API_DEFAULT_VERSION)
+        }
+        h if h.starts_with("tcp://") || h.starts_with("http://") => {
+            Docker::connect_with_http(h, CONNECTION_TIMEOUT, API_DEFAULT_VERSION)
+        }
+        _ => {
+            return Err(LdbError::invalid_input(format!(
+                "Unsupported engine address: {}",
+                host
+            )))
+        }
+    };
+
+    docker.map_err(LdbError::docker_unavailable)
+}
+
+/// Identify the engine behind a connection
+pub async fn detect(docker: &Docker) -> Result<EngineInfo, LdbError> {
+    let version = docker
+        .version()
+        .await
+        .map_err(|e| LdbError::docker("Failed to get engine version", e))?;
+
+    engine_info(docker, &version).await
+}
+
+/// Identify the engine from its version and system info
+pub async fn engine_info(docker: &Docker, version: &Version) -> Result<EngineInfo, LdbError> {
+    let info = docker
+        .info()
+        .await
+        .map_err(|e| LdbError::docker("Failed to get engine info", e))?;
+
+    Ok(EngineInfo::from_daemon(
+        version,
+        &info.security_options.unwrap_or_default(),
+    ))
+}
+
+/// Remove an instance volume directory
+///
+/// Under rootless engines the database files belong to subordinate UIDs the
+/// current user cannot delete directly; with Podman they are removed from
+/// inside its user namespace instead.
+pub fn remove_volume_dir(path: &Path, engine: &EngineInfo) -> Result<(), LdbError> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let error = match fs::remove_dir_all(path) {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+
+    if error.kind() != std::io::ErrorKind::PermissionDenied
+        || engine.kind != EngineKind::Podman
+        || !engine.rootless
+    {
+        return Err(LdbError::state("Failed to delete volume directory", error));
+    }
+
+    let status = Command::new("podman")
+        .args(["unshare", "rm", "-rf", "--"])
+        .arg(path)
+        .status()
+        .map_err(|e| LdbError::state("Failed to run podman unshare", e))?;
+
+    if !status.success() {
+        return Err(LdbError::state(
+            "Failed to delete volume directory",
+            format!("podman unshare rm exited with {}", status),
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bollard::models::EventMessage;
+use bollard::system::EventsOptions;
+use futures::StreamExt;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::docker::labels;
+use crate::docker::DockerClient;
+use crate::error::LdbError;
+use crate::models::instance::{Instance, InstanceStatus, InstanceStatusEvent};
+use crate::readiness;
+use crate::secrets;
+use crate::state::StateManager;
+
+/// Pause before resubscribing after the event stream ends or Docker is unreachable
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(2);
+
+/// Container actions that change an instance's status
+const WATCHED_ACTIONS: &[&str] = &["start", "die", "oom", "health_status"];
+
+/// Tell the UI an instance's status changed
+pub fn emit_status(app: &AppHandle, instance: &Instance, message: Option<String>) {
+    let _ = app.emit(
+        "instance-status-changed",
+        InstanceStatusEvent {
+            instance_id: instance.id,
+            status: instance.status,
+            message,
+        },
+    );
+}
+
+/// Follow Docker's event stream for our containers for the app's lifetime
+///
+/// Status changes are written to state and emitted as
+/// `instance-status-changed`. The subscription is re-established whenever
+/// the stream ends, e.g. because the daemon restarted.
+pub fn spawn_event_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            if let Err(e) = watch(&app).await {
+                eprintln!("Docker event stream interrupted: {}", e);
+            }
+
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        }
+    });
+}
+
+/// Subscribe once and handle events until the stream ends
+async fn watch(app: &AppHandle) -> Result<(), LdbError> {
+    let docker = app.state::<DockerClient>().connection().await?;
+
+    // Only containers we created; unlabeled legacy containers are picked
+    // up by list_instances instead
+    let filters = HashMap::from([
+        ("type".to_string(), vec!["container".to_string()]),
+        ("label".to_string(), vec![format!("{}=true", labels::LABEL_MANAGED)]),
+        (
+            "event".to_string(),
+            WATCHED_ACTIONS.iter().map(|a| a.to_string()).collect(),
+        ),
+    ]);
+
+    let mut stream = docker.events(Some(EventsOptions::<String> {
+        filters,
+        ..Default::default()
+    }));
+
+    while let Some(event) = stream.next().await {
+        let event = event.map_err(|e| LdbError::docker("Failed to read Docker events", e))?;
+        handle_event(app, event);
+    }
+
+    Ok(())
+}
+
+/// Apply one container event to the stored instance it belongs to
+fn handle_event(app: &AppHandle, event: EventMessage) {
+    let attributes = event.actor.and_then(|a| a.attributes).unwrap_or_default();
+    let Some(instance_id) = labels::instance_id(Some(&attributes)) else {
+        return;
+    };
+
+    let Some((status, message)) = status_for(event.action.as_deref().unwrap_or_default(), &attributes) else {
+        return;
+    };
+
+    let Ok(state_manager) = StateManager::new() else {
+        return;
+    };
+
+    // A start only means the database is on its way; keep a status that
+    // already says so (or better). A container the app stopped may still be
+    // killed once the stop timeout runs out; that is not an error either.
+    // Only the status is written, so concurrent changes to the rest of the
+    // instance are kept.
+    let starting = status == InstanceStatus::Starting;
+    let mut changed = false;
+    let Ok(updated) = state_manager.modify_instance(&instance_id.to_string(), |i| {
+        let keep = (starting && i.status == InstanceStatus::Ready)
+            || (status == InstanceStatus::Error && i.status == InstanceStatus::Stopped);
+        if i.status != status && !keep {
+            i.status = status;
+            changed = true;
+        }
+    }) else {
+        return;
+    };
+    if !changed {
+        return;
+    }
+    emit_status(app, &updated, message);
+
+    if starting {
+        spawn_readiness_wait(app.clone(), updated);
+    }
+}
+
+/// Map a container event to the instance status it implies
+fn status_for(action: &str, attributes: &HashMap<String, String>) -> Option<(InstanceStatus, Option<String>)> {
+    // Docker reports "health_status: healthy"; Podman puts it in an attribute
+    let (action, detail) = match action.split_once(':') {
+        Some((action, detail)) => (action.trim(), Some(detail.trim())),
+        None => (action, None),
+    };
+
+    match action {
+        "start" => Some((InstanceStatus::Starting, None)),
+        // JVM engines (Elasticsearch, OpenSearch) exit with 143 on the
+        // SIGTERM of a normal `docker stop`
+        "die" => match attributes.get("exitCode").map(String::as_str) {
+            Some("0") | Some("143") | None => Some((InstanceStatus::Stopped, None)),
+            Some(code) => Some((
+                InstanceStatus::Error,
+                Some(format!("Container exited with code {}", code)),
+            )),
+        },
+        "oom" => Some((
+            InstanceStatus::Error,
+            Some("Container ran out of memory".to_string()),
+        )),
+        "health_status" => {
+            match detail.or(attributes.get("health_status").map(String::as_str)) {
+                Some("healthy") => Some((InstanceStatus::Ready, None)),
+                Some("unhealthy") => Some((
+                    InstanceStatus::Unhealthy,
+                    Some("Health check failed".to_string()),
+                )),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Mark an instance ready once its database accepts connections
+fn spawn_readiness_wait(app: AppHandle, instance: Instance) {
+    tauri::async_runtime::spawn(async move {
+        let password = secrets::resolve(instance.password_ref.as_ref()).unwrap_or_default();
+        if readiness::wait_until_ready(&instance, &password, readiness::DEFAULT_TIMEOUT)
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        let Ok(state_manager) = StateManager::new() else {
+            return;
+        };
+        if let Ok(ready) = state_manager.modify_instance(&instance.id.to_string(), |i| {
+            if i.status == InstanceStatus::Starting {
+                i.status = InstanceStatus::Ready;
+            }
+        }) {
+            if ready.status == InstanceStatus::Ready {
+                emit_status(&app, &ready, None);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_for_maps_exit_codes() {
+        let mut attributes = HashMap::new();
+        assert_eq!(status_for("die", &attributes), Some((InstanceStatus::Stopped, None)));
+
+        attributes.insert("exitCode".to_string(), "143".to_string());
+        assert_eq!(status_for("die", &attributes), Some((InstanceStatus::Stopped, None)));
+
+        attributes.insert("exitCode".to_string(), "137".to_string());
+        let (status, message) = status_for("die", &attributes).unwrap();
+        assert_eq!(status, InstanceStatus::Error);
+        assert_eq!(message.as_deref(), Some("Container exited with code 137"));
+    }
+
+    #[test]
+    fn status_for_reads_health_from_docker_and_podman() {
+        let attributes = HashMap::new();
+        assert_eq!(
+            status_for("health_status: healthy", &attributes),
+            Some((InstanceStatus::Ready, None))
+        );
+
+        let podman = HashMap::from([("health_status".to_string(), "unhealthy".to_string())]);
+        assert_eq!(status_for("health_status", &podman).map(|(s, _)| s), Some(InstanceStatus::Unhealthy));
+
+        assert_eq!(status_for("exec_start", &attributes), None);
+    }
+}
@@ -2,7 +2,10 @@ use reqwest::Client;
 use serde::Deserialize;
 use std::time::Duration;
 
+use crate::error::LdbError;
+
 const DOCKER_HUB_API: &str = "https://hub.docker.com/v2";
+const MCR_HOST: &str = "mcr.microsoft.com";
 
 #[derive(Debug, Deserialize)]
 pub struct TagResponse {
@@ -19,6 +22,12 @@ pub struct Tag {
     pub digest: Option<String>,
 }
 
+/// Tag list of the registry v2 API, as served by MCR
+#[derive(Debug, Deserialize)]
+struct RegistryTagList {
+    tags: Vec<String>,
+}
+
 pub struct DockerHubClient {
     http: Client,
 }
@@ -35,7 +44,14 @@ impl DockerHubClient {
     }
 
     /// Fetch tags for a Docker Hub image (e.g., "library/postgres")
-    pub async fn get_tags(&self, image: &str, page: usize) -> Result<TagResponse, String> {
+    ///
+    /// Images on the Microsoft Container Registry ("mcr.microsoft.com/mssql/server")
+    /// are looked up there instead.
+    pub async fn get_tags(&self, image: &str, page: usize) -> Result<TagResponse, LdbError> {
+        if let Some(repository) = image.strip_prefix(MCR_HOST).and_then(|r| r.strip_prefix('/')) {
+            return self.get_mcr_tags(repository).await;
+        }
+
         let url = format!(
             "{}/repositories/{}/tags?page={}&page_size=20",
             DOCKER_HUB_API, image, page
@@ -47,16 +63,51 @@ impl DockerHubClient {
             .header("Accept", "application/json")
             .send()
             .await
-            .map_err(|e| format!("Failed to fetch tags: {}", e))?;
+            .map_err(|e| LdbError::registry("Failed to fetch tags", e))?;
 
         if !response.status().is_success() {
-            return Err(format!("Docker Hub API error: {}", response.status()));
+            return Err(LdbError::registry("Docker Hub API error", response.status()));
         }
 
         response
             .json::<TagResponse>()
             .await
-            .map_err(|e| format!("Failed to parse tags: {}", e))
+            .map_err(|e| LdbError::registry("Failed to parse tags", e))
+    }
+
+    /// Fetch tags from MCR, which has no Docker Hub-style API
+    ///
+    /// The registry returns every tag at once, so this is always a single page.
+    async fn get_mcr_tags(&self, repository: &str) -> Result<TagResponse, LdbError> {
+        let url = format!("https://{}/v2/{}/tags/list", MCR_HOST, repository);
+
+        let response = self
+            .http
+            .get(&url)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| LdbError::registry("Failed to fetch tags", e))?;
+
+        if !response.status().is_success() {
+            return Err(LdbError::registry("MCR API error", response.status()));
+        }
+
+        let list = response
+            .json::<RegistryTagList>()
+            .await
+            .map_err(|e| LdbError::registry("Failed to parse tags", e))?;
+
+        Ok(TagResponse {
+            count: list.tags.len(),
+            next: None,
+            previous: None,
+            results: list
+                .tags
+                .into_iter()
+                .map(|name| Tag { name, digest: None })
+                .collect(),
+        })
     }
 }
 
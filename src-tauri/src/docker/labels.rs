@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use bollard::container::ListContainersOptions;
+use bollard::models::ContainerSummary;
+use bollard::Docker;
+use uuid::Uuid;
+
+use crate::error::LdbError;
+use crate::models::instance::{DatabaseType, Instance};
+
+/// Marks a container as created and managed by LDB-Engine
+pub const LABEL_MANAGED: &str = "dev.ldb-engine.managed";
+/// ID of the stored instance the container belongs to
+pub const LABEL_INSTANCE_ID: &str = "dev.ldb-engine.instance-id";
+/// Database type of the instance (serde name, e.g. "postgresql")
+pub const LABEL_DATABASE_TYPE: &str = "dev.ldb-engine.database-type";
+/// Version of LDB-Engine that created the container
+pub const LABEL_APP_VERSION: &str = "dev.ldb-engine.app-version";
+
+/// Labels stamped on every container created for an instance
+pub fn for_instance(instance_id: &Uuid, database_type: &DatabaseType) -> HashMap<String, String> {
+    let database_type = serde_json::to_value(database_type)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+
+    HashMap::from([
+        (LABEL_MANAGED.to_string(), "true".to_string()),
+        (LABEL_INSTANCE_ID.to_string(), instance_id.to_string()),
+        (LABEL_DATABASE_TYPE.to_string(), database_type),
+        (LABEL_APP_VERSION.to_string(), env!("CARGO_PKG_VERSION").to_string()),
+    ])
+}
+
+/// Read the instance ID from a container's labels, if it is one of ours
+pub fn instance_id(labels: Option<&HashMap<String, String>>) -> Option<Uuid> {
+    let labels = labels?;
+
+    if labels.get(LABEL_MANAGED).map(String::as_str) != Some("true") {
+        return None;
+    }
+
+    labels
+        .get(LABEL_INSTANCE_ID)
+        .and_then(|id| Uuid::parse_str(id).ok())
+}
+
+/// Whether a container carries any LDB-Engine label
+pub fn is_labeled(labels: Option<&HashMap<String, String>>) -> bool {
+    labels.is_some_and(|labels| labels.contains_key(LABEL_MANAGED))
+}
+
+/// Container name for a new instance
+///
+/// Purely cosmetic - lookups go through labels. The ID suffix keeps names
+/// like "My DB" and "my-db" from colliding.
+pub fn container_name(name: &str, instance_id: &Uuid) -> String {
+    let id = instance_id.simple().to_string();
+    format!("{}-{}", legacy_container_name(name), &id[..8])
+}
+
+/// Name containers were given before labels existed
+///
+/// Only used to find unlabeled containers of instances created by older
+/// versions; Docker does not allow adding labels to an existing container.
+pub fn legacy_container_name(name: &str) -> String {
+    format!("ldb-{}", name.replace(' ', "-").to_lowercase())
+}
+
+/// Find the container belonging to a stored instance
+///
+/// Resolves by instance-ID label, falling back to the legacy name for
+/// unlabeled containers created by older versions.
+pub async fn find_container(
+    docker: &Docker,
+    instance: &Instance,
+) -> Result<Option<ContainerSummary>, LdbError> {
+    let filters = HashMap::from([(
+        "label".to_string(),
+        vec![format!("{}={}", LABEL_INSTANCE_ID, instance.id)],
+    )]);
+
+    let labeled = docker
+        .list_containers(Some(ListContainersOptions::<String> {
+            all: true,
+            filters,
+            ..Default::default()
+        }))
+        .await
+        .map_err(|e| LdbError::docker("Failed to list containers", e))?;
+
+    if let Some(container) = labeled.into_iter().next() {
+        return Ok(Some(container));
+    }
+
+    let legacy_name = legacy_container_name(&instance.name);
+    let filters = HashMap::from([("name".to_string(), vec![legacy_name.clone()])]);
+
+    let named = docker
+        .list_containers(Some(ListContainersOptions::<String> {
+            all: true,
+            filters,
+            ..Default::default()
+        }))
+        .await
+        .map_err(|e| LdbError::docker("Failed to list containers", e))?;
+
+    // The name filter is a substring match, so check the exact name
+    Ok(named.into_iter().find(|c| {
+        !is_labeled(c.labels.as_ref())
+            && c.names
+                .as_ref()
+                .is_some_and(|names| names.iter().any(|n| n.trim_start_matches('/') == legacy_name))
+    }))
+}
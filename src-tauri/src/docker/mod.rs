@@ -3,6 +3,9 @@
 /// Provides Docker Hub API and Docker daemon client functionality.
 pub mod hub;
 pub mod client;
+pub mod engine;
+pub mod events;
+pub mod labels;
 
 pub use hub::DockerHubClient;
 pub use client::DockerClient;
@@ -0,0 +1,214 @@
+use std::fmt::Display;
+
+use serde::ser::{SerializeMap, Serializer};
+use serde::Serialize;
+
+/// Error returned by every Tauri command
+///
+/// Serialized for the frontend as `{ code, message, ...fields }`, where
+/// `code` is a stable SCREAMING_SNAKE_CASE identifier to match on and
+/// `message` is human-readable text for display.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum LdbError {
+    /// The Docker daemon could not be reached
+    #[error("Docker is not available: {message}")]
+    DockerUnavailable { message: String },
+
+    /// A Docker API call failed
+    #[error("{message}")]
+    Docker { message: String },
+
+    /// The requested host port is taken
+    #[error("Port {port} is already in use")]
+    PortInUse { port: u16 },
+
+    /// Another process took an instance's host port while it was stopped
+    #[error("Port {port} of instance {id} is taken by another process; port {suggested_port} is free")]
+    PortConflict {
+        id: String,
+        port: u16,
+        suggested_port: u16,
+    },
+
+    /// No free port was found in the searched range
+    #[error("No available ports found from {start}")]
+    NoPortAvailable { start: u16 },
+
+    /// The image has not been pulled yet
+    #[error("Image {image} is not available locally; pull it first")]
+    ImageMissing { image: String },
+
+    /// No stored instance has the given ID
+    #[error("Instance not found: {id}")]
+    InstanceNotFound { id: String },
+
+    /// The database did not accept connections within the readiness timeout
+    #[error("Instance {id} did not become ready within {timeout_secs} seconds")]
+    NotReady { id: String, timeout_secs: u64 },
+
+    /// No container exists for the given ID, name or instance
+    #[error("Container not found: {id}")]
+    ContainerNotFound { id: String },
+
+    /// The volume would not be mounted where the image writes its data
+    #[error("Data would not be persisted: {image} stores data in {expected}, but the volume is mounted at {data_path}")]
+    DataNotPersisted {
+        image: String,
+        data_path: String,
+        expected: String,
+    },
+
+    /// Stored state could not be read and no good copy was available
+    #[error("State is corrupt: {message}")]
+    StateCorrupt { message: String },
+
+    /// The state database was written by a newer version of the app
+    #[error("State database schema v{version} is newer than this app supports (v{supported}); update the app")]
+    StateTooNew { version: usize, supported: usize },
+
+    /// Reading or writing the state database failed
+    #[error("{message}")]
+    State { message: String },
+
+    /// Reading or writing a secret failed
+    #[error("{message}")]
+    Secret { message: String },
+
+    /// The image registry could not be queried
+    #[error("{message}")]
+    Registry { message: String },
+
+    /// The request was rejected before reaching Docker
+    #[error("{message}")]
+    InvalidInput { message: String },
+
+    /// Any other failure
+    #[error("{message}")]
+    Internal { message: String },
+}
+
+impl LdbError {
+    /// Stable identifier for the error kind
+    pub fn code(&self) -> &'static str {
+        match self {
+            LdbError::DockerUnavailable { .. } => "DOCKER_UNAVAILABLE",
+            LdbError::Docker { .. } => "DOCKER_ERROR",
+            LdbError::PortInUse { .. } => "PORT_IN_USE",
+            LdbError::PortConflict { .. } => "PORT_CONFLICT",
+            LdbError::NoPortAvailable { .. } => "NO_PORT_AVAILABLE",
+            LdbError::ImageMissing { .. } => "IMAGE_MISSING",
+            LdbError::InstanceNotFound { .. } => "INSTANCE_NOT_FOUND",
+            LdbError::NotReady { .. } => "NOT_READY",
+            LdbError::ContainerNotFound { .. } => "CONTAINER_NOT_FOUND",
+            LdbError::DataNotPersisted { .. } => "DATA_NOT_PERSISTED",
+            LdbError::StateCorrupt { .. } => "STATE_CORRUPT",
+            LdbError::StateTooNew { .. } => "STATE_TOO_NEW",
+            LdbError::State { .. } => "STATE_ERROR",
+            LdbError::Secret { .. } => "SECRET_ERROR",
+            LdbError::Registry { .. } => "REGISTRY_ERROR",
+            LdbError::InvalidInput { .. } => "INVALID_INPUT",
+            LdbError::Internal { .. } => "INTERNAL_ERROR",
+        }
+    }
+
+    /// A failed Docker API call, with context
+    pub fn docker(context: &str, error: impl Display) -> Self {
+        LdbError::Docker {
+            message: format!("{}: {}", context, error),
+        }
+    }
+
+    /// The Docker daemon could not be reached
+    pub fn docker_unavailable(error: impl Display) -> Self {
+        LdbError::DockerUnavailable {
+            message: error.to_string(),
+        }
+    }
+
+    /// A failed state read or write, with context
+    pub fn state(context: &str, error: impl Display) -> Self {
+        LdbError::State {
+            message: format!("{}: {}", context, error),
+        }
+    }
+
+    /// Stored state that cannot be parsed, with context
+    pub fn state_corrupt(context: &str, error: impl Display) -> Self {
+        LdbError::StateCorrupt {
+            message: format!("{}: {}", context, error),
+        }
+    }
+
+    /// A failed secret store operation, with context
+    pub fn secret(context: &str, error: impl Display) -> Self {
+        LdbError::Secret {
+            message: format!("{}: {}", context, error),
+        }
+    }
+
+    /// A failed registry request, with context
+    pub fn registry(context: &str, error: impl Display) -> Self {
+        LdbError::Registry {
+            message: format!("{}: {}", context, error),
+        }
+    }
+
+    /// A rejected request
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        LdbError::InvalidInput {
+            message: message.into(),
+        }
+    }
+
+    /// Any other failure, with context
+    pub fn internal(context: &str, error: impl Display) -> Self {
+        LdbError::Internal {
+            message: format!("{}: {}", context, error),
+        }
+    }
+}
+
+impl Serialize for LdbError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", &self.to_string())?;
+
+        match self {
+            LdbError::PortInUse { port } => map.serialize_entry("port", port)?,
+            LdbError::PortConflict { id, port, suggested_port } => {
+                map.serialize_entry("id", id)?;
+                map.serialize_entry("port", port)?;
+                map.serialize_entry("suggested_port", suggested_port)?;
+            }
+            LdbError::NoPortAvailable { start } => map.serialize_entry("start", start)?,
+            LdbError::ImageMissing { image } => map.serialize_entry("image", image)?,
+            LdbError::InstanceNotFound { id } | LdbError::ContainerNotFound { id } => {
+                map.serialize_entry("id", id)?
+            }
+            LdbError::NotReady { id, timeout_secs } => {
+                map.serialize_entry("id", id)?;
+                map.serialize_entry("timeout_secs", timeout_secs)?;
+            }
+            LdbError::DataNotPersisted { image, data_path, expected } => {
+                map.serialize_entry("image", image)?;
+                map.serialize_entry("data_path", data_path)?;
+                map.serialize_entry("expected", expected)?;
+            }
+            LdbError::StateTooNew { version, supported } => {
+                map.serialize_entry("version", version)?;
+                map.serialize_entry("supported", supported)?;
+            }
+            LdbError::DockerUnavailable { .. }
+            | LdbError::Docker { .. }
+            | LdbError::StateCorrupt { .. }
+            | LdbError::State { .. }
+            | LdbError::Secret { .. }
+            | LdbError::Registry { .. }
+            | LdbError::InvalidInput { .. }
+            | LdbError::Internal { .. } => {}
+        }
+
+        map.end()
+    }
+}
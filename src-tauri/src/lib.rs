@@ -1,7 +1,10 @@
 // Docker module for container management
 pub mod docker;
 pub mod commands;
+pub mod error;
 pub mod models;
+pub mod readiness;
+pub mod secrets;
 pub mod state;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -15,12 +18,19 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
+        .manage(docker::DockerClient::new())
+        .setup(|app| {
+            docker::client::spawn_health_monitor(app.handle().clone());
+            docker::events::spawn_event_watcher(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             commands::images::get_docker_tags,
             commands::images::get_supported_images,
             commands::images::pull_docker_image,
             commands::instances::create_instance,
+            commands::instances::migrate_port_mappings,
             commands::instances::start_instance,
             commands::instances::stop_instance,
             commands::instances::restart_instance,
@@ -29,10 +39,22 @@ pub fn run() {
             commands::instances::get_container_status_string,
             commands::instances::get_instance_volume_path,
             commands::connections::get_connection_string,
+            commands::connections::get_connection_strings,
+            commands::docker::get_docker_status,
+            commands::docker::list_container_engines,
+            commands::docker::get_container_engine,
+            commands::docker::set_container_engine,
             commands::logs::stream_container_logs,
             commands::ports::get_occupied_ports,
             commands::ports::get_available_port,
             commands::ports::get_next_port_for_type,
+            commands::ports::allocate_instance_ports,
+            commands::ports::get_port_range,
+            commands::ports::set_port_range,
+            commands::reconcile::adopt_container,
+            commands::reconcile::reconcile_instances,
+            commands::reconcile::resolve_discrepancy,
+            commands::volumes::check_data_persistence,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
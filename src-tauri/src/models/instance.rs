@@ -1,7 +1,17 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::secret::SecretRef;
+
+/// Name of an instance's main port in port maps
+pub const PRIMARY_PORT: &str = "primary";
+
 /// Supported database types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -9,9 +19,24 @@ pub enum DatabaseType {
     #[serde(alias = "postgres")]
     PostgreSQL,
     Redis,
+    Valkey,
+    KeyDB,
     MySQL,
+    MariaDB,
     #[serde(alias = "mongo")]
     MongoDB,
+    ClickHouse,
+    Elasticsearch,
+    OpenSearch,
+    #[serde(alias = "mssql")]
+    SqlServer,
+}
+
+impl DatabaseType {
+    /// Whether the engine is Redis or a fork that speaks its protocol
+    pub fn is_redis_compatible(&self) -> bool {
+        matches!(self, DatabaseType::Redis | DatabaseType::Valkey | DatabaseType::KeyDB)
+    }
 }
 
 impl Default for DatabaseType {
@@ -20,14 +45,143 @@ impl Default for DatabaseType {
     }
 }
 
+/// Host address an instance's port is published on
+///
+/// Serialized as "loopback", "loopback_dual_stack", "all" or an IP address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum BindAddress {
+    /// 127.0.0.1 only, unreachable from other machines
+    #[default]
+    Loopback,
+    /// 127.0.0.1 and ::1
+    LoopbackDualStack,
+    /// Every interface (0.0.0.0); what instances got before this was configurable
+    All,
+    /// A single interface address
+    Ip(IpAddr),
+}
+
+impl BindAddress {
+    /// Host IPs to publish the port on, as Docker port bindings expect them
+    pub fn host_ips(&self) -> Vec<String> {
+        match self {
+            BindAddress::Loopback => vec![Ipv4Addr::LOCALHOST.to_string()],
+            BindAddress::LoopbackDualStack => {
+                vec![Ipv4Addr::LOCALHOST.to_string(), Ipv6Addr::LOCALHOST.to_string()]
+            }
+            BindAddress::All => vec![Ipv4Addr::UNSPECIFIED.to_string()],
+            BindAddress::Ip(ip) => vec![ip.to_string()],
+        }
+    }
+
+    /// Address clients on this machine connect to
+    pub fn connect_ip(&self) -> IpAddr {
+        match self {
+            BindAddress::Ip(IpAddr::V6(ip)) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            BindAddress::Ip(ip) if !ip.is_unspecified() => *ip,
+            _ => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        }
+    }
+
+    /// `connect_ip` as the host part of a URL, with IPv6 in brackets
+    pub fn connect_host(&self) -> String {
+        match self.connect_ip() {
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) => format!("[{}]", ip),
+        }
+    }
+
+    /// Infer the bind address from the host IPs of a container's port bindings
+    pub fn from_host_ips(host_ips: &[String]) -> Self {
+        let ips: Vec<IpAddr> = host_ips.iter().filter_map(|ip| ip.parse().ok()).collect();
+
+        match ips.as_slice() {
+            [] => BindAddress::All,
+            [ip] if ip.is_unspecified() => BindAddress::All,
+            [IpAddr::V4(ip)] if *ip == Ipv4Addr::LOCALHOST => BindAddress::Loopback,
+            [ip] => BindAddress::Ip(*ip),
+            _ if ips.iter().any(|ip| ip.is_unspecified()) => BindAddress::All,
+            _ if ips.iter().all(|ip| ip.is_loopback()) => BindAddress::LoopbackDualStack,
+            [ip, ..] => BindAddress::Ip(*ip),
+        }
+    }
+}
+
+impl fmt::Display for BindAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BindAddress::Loopback => write!(f, "loopback"),
+            BindAddress::LoopbackDualStack => write!(f, "loopback_dual_stack"),
+            BindAddress::All => write!(f, "all"),
+            BindAddress::Ip(ip) => write!(f, "{}", ip),
+        }
+    }
+}
+
+impl FromStr for BindAddress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "loopback" => Ok(BindAddress::Loopback),
+            "loopback_dual_stack" => Ok(BindAddress::LoopbackDualStack),
+            "all" => Ok(BindAddress::All),
+            ip => ip
+                .parse()
+                .map(BindAddress::Ip)
+                .map_err(|_| format!("Invalid bind address: {}", ip)),
+        }
+    }
+}
+
+impl From<BindAddress> for String {
+    fn from(address: BindAddress) -> Self {
+        address.to_string()
+    }
+}
+
+impl TryFrom<String> for BindAddress {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Legacy records predate the setting; their containers listen everywhere
+fn legacy_bind_address() -> BindAddress {
+    BindAddress::All
+}
+
 /// Instance status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum InstanceStatus {
+    /// Container is running; the database has not been probed
     Running,
     Stopped,
     Error,
     Creating,
+    /// Container is running but the database does not accept connections yet
+    Starting,
+    /// Database accepts connections
+    Ready,
+    /// Container is running but its health check is failing
+    Unhealthy,
+}
+
+impl InstanceStatus {
+    /// Whether the container is up, whatever the database's readiness
+    pub fn is_running(&self) -> bool {
+        matches!(
+            self,
+            InstanceStatus::Running
+                | InstanceStatus::Starting
+                | InstanceStatus::Ready
+                | InstanceStatus::Unhealthy
+        )
+    }
 }
 
 impl Default for InstanceStatus {
@@ -45,11 +199,26 @@ pub struct Instance {
     pub image: String,
     pub tag: String,
     pub port: u16,
-    pub root_password: String,
+    /// Host address the port is published on
+    #[serde(default = "legacy_bind_address")]
+    pub bind_address: BindAddress,
+    /// Host ports of additional named endpoints, e.g. ClickHouse's "native" port
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extra_ports: BTreeMap<String, u16>,
+    /// Root password, held in the secret store; `None` if the instance has no password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_ref: Option<SecretRef>,
     pub status: InstanceStatus,
     pub created_at: DateTime<Utc>,
+    /// Host directory holding the data, or a named volume for adopted containers
     #[serde(skip_serializing_if = "Option::is_none")]
     pub volume_path: Option<String>,
+    /// `volume_path` is an adopted container's own mount, which the app must never delete
+    #[serde(default)]
+    pub adopted_mount: bool,
+    /// Docker container name, filled in from Docker; not persisted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
 }
 
 impl Instance {
@@ -59,7 +228,6 @@ impl Instance {
         image: String,
         tag: String,
         port: u16,
-        root_password: String,
     ) -> Self {
         Self {
             id: Uuid::new_v4(),
@@ -68,14 +236,52 @@ impl Instance {
             image,
             tag,
             port,
-            root_password,
+            bind_address: BindAddress::default(),
+            extra_ports: BTreeMap::new(),
+            password_ref: None,
             status: InstanceStatus::Stopped,
             created_at: Utc::now(),
             volume_path: None,
+            adopted_mount: false,
+            container_name: None,
+        }
+    }
+
+    /// Host ports by name, the main port under "primary"
+    pub fn ports(&self) -> BTreeMap<String, u16> {
+        let mut ports = self.extra_ports.clone();
+        ports.insert(PRIMARY_PORT.to_string(), self.port);
+        ports
+    }
+
+    /// Set host ports by name, as returned by `ports`
+    pub fn set_ports(&mut self, mut ports: BTreeMap<String, u16>) {
+        if let Some(port) = ports.remove(PRIMARY_PORT) {
+            self.port = port;
         }
+        self.extra_ports = ports;
     }
 }
 
+/// Payload of the `instance-status-changed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceStatusEvent {
+    pub instance_id: Uuid,
+    pub status: InstanceStatus,
+    /// Why the status changed, when there is more to say than the status itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Payload of the `instance-port-changed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct InstancePortChangedEvent {
+    pub instance_id: Uuid,
+    pub port: u16,
+    pub ports: BTreeMap<String, u16>,
+    pub connection_string: String,
+}
+
 /// Request to create a new instance
 #[derive(Debug, Clone, Deserialize)]
 pub struct CreateInstanceRequest {
@@ -86,4 +292,40 @@ pub struct CreateInstanceRequest {
     pub password: String,
     #[serde(default)]
     pub port: Option<u16>,
+    /// Host ports wanted for named endpoints, e.g. `{"native": 19000}`; the rest are picked automatically
+    #[serde(default)]
+    pub ports: BTreeMap<String, u16>,
+    /// Host address to publish the port on; loopback unless asked otherwise
+    #[serde(default)]
+    pub bind_address: BindAddress,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ips(ips: &[&str]) -> Vec<String> {
+        ips.iter().map(|ip| ip.to_string()).collect()
+    }
+
+    #[test]
+    fn from_host_ips_round_trips_host_ips() {
+        for bind in [
+            BindAddress::Loopback,
+            BindAddress::LoopbackDualStack,
+            BindAddress::All,
+            BindAddress::Ip("192.168.1.10".parse().unwrap()),
+        ] {
+            assert_eq!(BindAddress::from_host_ips(&bind.host_ips()), bind);
+        }
+    }
+
+    #[test]
+    fn from_host_ips_infers_legacy_bindings() {
+        // Docker reports no host IP, or "" for ports published everywhere
+        assert_eq!(BindAddress::from_host_ips(&[]), BindAddress::All);
+        assert_eq!(BindAddress::from_host_ips(&ips(&[""])), BindAddress::All);
+        assert_eq!(BindAddress::from_host_ips(&ips(&["0.0.0.0", "::"])), BindAddress::All);
+        assert_eq!(BindAddress::from_host_ips(&ips(&["::1"])), BindAddress::Ip("::1".parse().unwrap()));
+    }
 }
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// Where a secret is physically stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretBackend {
+    /// OS secret service (freedesktop Secret Service / kernel keyring, Keychain, Credential Manager)
+    Keyring,
+    /// AES-GCM encrypted file under ~/.ldb-engine, for machines without a secret service
+    EncryptedFile,
+}
+
+/// Reference to a secret held outside the state database
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SecretRef {
+    pub backend: SecretBackend,
+    pub key: String,
+}
@@ -0,0 +1,81 @@
+/// Readiness probing
+///
+/// Docker reports a container as running as soon as its entrypoint starts,
+/// long before the database accepts connections. These probes speak just
+/// enough of each wire protocol to tell the two apart.
+pub mod probes;
+
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use tokio::net::TcpStream;
+use tokio::time::{sleep, timeout, Instant};
+
+use crate::error::LdbError;
+use crate::models::instance::{DatabaseType, Instance};
+
+/// How long to wait for a database to accept connections by default
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Pause between probes while waiting
+const PROBE_INTERVAL: Duration = Duration::from_millis(500);
+/// Search engines answer this once shards can serve requests
+const SEARCH_HEALTH_PATH: &str = "/_cluster/health?wait_for_status=yellow&timeout=1s";
+/// Upper bound on a single probe, connect included
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Probe once whether an instance's database accepts connections
+///
+/// Connects the way a client on this machine would, through the address the
+/// instance's port is published on.
+pub async fn probe(instance: &Instance, password: &str) -> bool {
+    let attempt = async {
+        let mut stream = TcpStream::connect((instance.bind_address.connect_ip(), instance.port)).await?;
+
+        match instance.database_type {
+            DatabaseType::PostgreSQL => probes::postgres(&mut stream).await,
+            DatabaseType::Redis | DatabaseType::Valkey | DatabaseType::KeyDB => {
+                probes::redis(&mut stream, password).await
+            }
+            DatabaseType::MySQL | DatabaseType::MariaDB => probes::mysql(&mut stream).await,
+            DatabaseType::MongoDB => probes::mongodb(&mut stream).await,
+            DatabaseType::ClickHouse => probes::http_ok(&mut stream, "/ping", None).await,
+            DatabaseType::Elasticsearch => {
+                let auth = basic_auth("elastic", password);
+                probes::http_ok(&mut stream, SEARCH_HEALTH_PATH, auth.as_deref()).await
+            }
+            DatabaseType::OpenSearch => {
+                let auth = basic_auth("admin", password);
+                probes::http_ok(&mut stream, SEARCH_HEALTH_PATH, auth.as_deref()).await
+            }
+            DatabaseType::SqlServer => probes::sqlserver(&mut stream).await,
+        }
+    };
+
+    matches!(timeout(PROBE_TIMEOUT, attempt).await, Ok(Ok(true)))
+}
+
+/// Basic auth credentials for `probes::http_ok`; none without a password
+fn basic_auth(user: &str, password: &str) -> Option<String> {
+    (!password.is_empty()).then(|| BASE64.encode(format!("{}:{}", user, password)))
+}
+
+/// Probe an instance until its database accepts connections or `wait` runs out
+pub async fn wait_until_ready(instance: &Instance, password: &str, wait: Duration) -> Result<(), LdbError> {
+    let deadline = Instant::now() + wait;
+
+    loop {
+        if probe(instance, password).await {
+            return Ok(());
+        }
+
+        if Instant::now() + PROBE_INTERVAL > deadline {
+            return Err(LdbError::NotReady {
+                id: instance.id.to_string(),
+                timeout_secs: wait.as_secs(),
+            });
+        }
+
+        sleep(PROBE_INTERVAL).await;
+    }
+}
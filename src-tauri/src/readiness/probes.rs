@@ -0,0 +1,313 @@
+use std::io;
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Largest reply a probe reads; a longer one is not from the server we expect
+const MAX_REPLY_LEN: usize = 64 * 1024;
+
+/// Length of a reply body from its length prefix, which counts `header` bytes
+/// already read
+fn body_len(len: i32, header: usize) -> io::Result<usize> {
+    usize::try_from(len)
+        .ok()
+        .and_then(|len| len.checked_sub(header))
+        .filter(|len| *len <= MAX_REPLY_LEN)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid reply length {}", len)))
+}
+
+/// Postgres: send a startup packet, as pg_isready does
+///
+/// Any authentication request means the server accepts connections; an
+/// error is only "not ready" when it is 57P03 (the database system is
+/// starting up or shutting down).
+pub async fn postgres(stream: &mut TcpStream) -> io::Result<bool> {
+    let mut params = Vec::new();
+    for value in ["user", "postgres", "database", "postgres"] {
+        params.extend_from_slice(value.as_bytes());
+        params.push(0);
+    }
+    params.push(0);
+
+    let mut packet = Vec::new();
+    packet.extend_from_slice(&(8 + params.len() as i32).to_be_bytes());
+    packet.extend_from_slice(&196608i32.to_be_bytes()); // protocol 3.0
+    packet.extend_from_slice(&params);
+    stream.write_all(&packet).await?;
+
+    let tag = stream.read_u8().await?;
+    match tag {
+        b'R' => Ok(true),
+        b'E' => {
+            let len = stream.read_i32().await?;
+            let mut body = vec![0; body_len(len, 4)?];
+            stream.read_exact(&mut body).await?;
+
+            // Fields are a type byte followed by a NUL-terminated string
+            let starting_up = body
+                .split(|b| *b == 0)
+                .any(|field| field == b"C57P03");
+            Ok(!starting_up)
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Redis (and compatible servers): authenticate if needed, then PING
+///
+/// Errors such as NOAUTH still mean the server is serving requests; only
+/// LOADING, BUSY and MASTERDOWN mean it is not ready yet.
+pub async fn redis(stream: &mut TcpStream, password: &str) -> io::Result<bool> {
+    let mut reader = BufReader::new(stream);
+
+    if !password.is_empty() {
+        reader
+            .get_mut()
+            .write_all(&resp_command(&["AUTH", password]))
+            .await?;
+        read_line(&mut reader).await?;
+    }
+
+    reader.get_mut().write_all(&resp_command(&["PING"])).await?;
+    let reply = read_line(&mut reader).await?;
+
+    let not_ready = ["-LOADING", "-BUSY", "-MASTERDOWN"]
+        .iter()
+        .any(|prefix| reply.starts_with(prefix));
+    Ok(reply.starts_with('+') || (reply.starts_with('-') && !not_ready))
+}
+
+fn resp_command(args: &[&str]) -> Vec<u8> {
+    let mut command = format!("*{}\r\n", args.len());
+    for arg in args {
+        command.push_str(&format!("${}\r\n{}\r\n", arg.len(), arg));
+    }
+    command.into_bytes()
+}
+
+async fn read_line(reader: &mut BufReader<&mut TcpStream>) -> io::Result<String> {
+    let mut line = String::new();
+    if (&mut *reader).take(MAX_REPLY_LEN as u64).read_line(&mut line).await? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(line)
+}
+
+/// MySQL (and MariaDB): the server greets every client with a handshake
+///
+/// A handshake packet (protocol version 10) means it is ready; an error
+/// packet (e.g. too many connections) means it is not.
+pub async fn mysql(stream: &mut TcpStream) -> io::Result<bool> {
+    // 3-byte payload length and 1-byte sequence number, then the payload
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+
+    let first = stream.read_u8().await?;
+    Ok(first == 0x0a)
+}
+
+/// HTTP servers (ClickHouse, search engines): GET a health path and expect 200
+///
+/// `auth` is sent as a basic `Authorization` header value when given.
+pub async fn http_ok(stream: &mut TcpStream, path: &str, auth: Option<&str>) -> io::Result<bool> {
+    let mut request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n", path);
+    if let Some(auth) = auth {
+        request.push_str(&format!("Authorization: Basic {}\r\n", auth));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut reader = BufReader::new(stream);
+    let status_line = read_line(&mut reader).await?;
+
+    // "HTTP/1.1 200 OK"
+    Ok(status_line.split_whitespace().nth(1) == Some("200"))
+}
+
+/// SQL Server: send a TDS PRELOGIN packet
+///
+/// The server answers with a tabular response (packet type 4) once it
+/// accepts connections.
+pub async fn sqlserver(stream: &mut TcpStream) -> io::Result<bool> {
+    const PRELOGIN: u8 = 0x12;
+    const TABULAR_RESULT: u8 = 0x04;
+
+    // Option table: VERSION (token 0) at offset 6, length 6, then the terminator
+    let mut payload = vec![0x00, 0x00, 0x06, 0x00, 0x06, 0xff];
+    payload.extend_from_slice(&[0; 6]); // client version, ignored by the server
+
+    let mut packet = vec![PRELOGIN, 0x01]; // status: end of message
+    packet.extend_from_slice(&(8 + payload.len() as u16).to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 1, 0]); // SPID, packet ID, window
+    packet.extend_from_slice(&payload);
+    stream.write_all(&packet).await?;
+
+    Ok(stream.read_u8().await? == TABULAR_RESULT)
+}
+
+/// MongoDB: run `hello` against the admin database over OP_MSG
+///
+/// `hello` needs no authentication and answers `ok: 1` once the server
+/// accepts connections.
+pub async fn mongodb(stream: &mut TcpStream) -> io::Result<bool> {
+    const OP_MSG: i32 = 2013;
+
+    let mut document = Vec::new();
+    bson_int32(&mut document, "hello", 1);
+    bson_string(&mut document, "$db", "admin");
+    let document = bson_document(document);
+
+    let mut message = Vec::new();
+    message.extend_from_slice(&0u32.to_le_bytes()); // flag bits
+    message.push(0); // section kind 0: body
+    message.extend_from_slice(&document);
+
+    let mut packet = Vec::new();
+    packet.extend_from_slice(&(16 + message.len() as i32).to_le_bytes());
+    packet.extend_from_slice(&1i32.to_le_bytes()); // request ID
+    packet.extend_from_slice(&0i32.to_le_bytes()); // response to
+    packet.extend_from_slice(&OP_MSG.to_le_bytes());
+    packet.extend_from_slice(&message);
+    stream.write_all(&packet).await?;
+
+    let len = stream.read_i32_le().await?;
+    let mut reply = vec![0; body_len(len, 4)?];
+    stream.read_exact(&mut reply).await?;
+    if reply.len() < 17 {
+        return Ok(false);
+    }
+
+    // Skip the rest of the header, the flag bits and the section kind
+    let op_code = i32::from_le_bytes([reply[8], reply[9], reply[10], reply[11]]);
+    if op_code != OP_MSG {
+        return Ok(false);
+    }
+
+    Ok(bson_ok(&reply[17..]))
+}
+
+fn bson_int32(document: &mut Vec<u8>, name: &str, value: i32) {
+    document.push(0x10);
+    document.extend_from_slice(name.as_bytes());
+    document.push(0);
+    document.extend_from_slice(&value.to_le_bytes());
+}
+
+fn bson_string(document: &mut Vec<u8>, name: &str, value: &str) {
+    document.push(0x02);
+    document.extend_from_slice(name.as_bytes());
+    document.push(0);
+    document.extend_from_slice(&(value.len() as i32 + 1).to_le_bytes());
+    document.extend_from_slice(value.as_bytes());
+    document.push(0);
+}
+
+fn bson_document(elements: Vec<u8>) -> Vec<u8> {
+    let mut document = (elements.len() as i32 + 5).to_le_bytes().to_vec();
+    document.extend_from_slice(&elements);
+    document.push(0);
+    document
+}
+
+/// Whether a BSON reply document has a top-level `ok` equal to 1
+fn bson_ok(document: &[u8]) -> bool {
+    let mut pos = 4; // document length
+
+    while pos < document.len() {
+        let element_type = document[pos];
+        if element_type == 0 {
+            break;
+        }
+        pos += 1;
+
+        let Some(name_len) = document[pos..].iter().position(|b| *b == 0) else {
+            return false;
+        };
+        let name = &document[pos..pos + name_len];
+        pos += name_len + 1;
+
+        let read_i32 = |at: usize| {
+            document
+                .get(at..at.checked_add(4)?)
+                .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        };
+        let read_len = |at: usize| read_i32(at).and_then(|len| usize::try_from(len).ok());
+
+        if name == b"ok" {
+            return match element_type {
+                0x01 => document
+                    .get(pos..pos.saturating_add(8))
+                    .map(|b| f64::from_le_bytes(b.try_into().unwrap_or_default()) == 1.0)
+                    .unwrap_or(false),
+                0x10 => read_i32(pos) == Some(1),
+                0x08 => document.get(pos) == Some(&1),
+                _ => false,
+            };
+        }
+
+        // Skip the value of any other element
+        let size = match element_type {
+            0x01 | 0x09 | 0x11 | 0x12 => Some(8),
+            0x02 | 0x0D | 0x0E => read_len(pos).and_then(|len| len.checked_add(4)),
+            0x03 | 0x04 => read_len(pos),
+            0x05 => read_len(pos).and_then(|len| len.checked_add(5)),
+            0x07 => Some(12),
+            0x08 => Some(1),
+            0x0A => Some(0),
+            0x10 => Some(4),
+            0x13 => Some(16),
+            _ => None,
+        };
+        let Some(next) = size.and_then(|size| pos.checked_add(size)) else {
+            return false;
+        };
+        pos = next;
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(elements: &[(&str, i32)]) -> Vec<u8> {
+        let mut document = Vec::new();
+        for (name, value) in elements {
+            bson_int32(&mut document, name, *value);
+        }
+        bson_document(document)
+    }
+
+    #[test]
+    fn body_len_rejects_out_of_range_lengths() {
+        assert_eq!(body_len(12, 4).unwrap(), 8);
+        assert!(body_len(2, 4).is_err());
+        assert!(body_len(-1, 4).is_err());
+        assert!(body_len(i32::MAX, 4).is_err());
+    }
+
+    #[test]
+    fn bson_ok_finds_ok_after_other_elements() {
+        let mut document = Vec::new();
+        bson_string(&mut document, "msg", "hi");
+        bson_int32(&mut document, "ok", 1);
+        assert!(bson_ok(&bson_document(document)));
+
+        assert!(!bson_ok(&reply(&[("ok", 0)])));
+        assert!(!bson_ok(&reply(&[("other", 1)])));
+    }
+
+    #[test]
+    fn bson_ok_rejects_malformed_lengths() {
+        // A string whose length would run past the end, or overflow
+        for len in [i32::MAX, -1] {
+            let mut document = vec![0, 0, 0, 0, 0x02, b'a', 0];
+            document.extend_from_slice(&len.to_le_bytes());
+            document.extend_from_slice(&[0x10, b'o', b'k', 0, 1, 0, 0, 0, 0]);
+            assert!(!bson_ok(&document));
+        }
+
+        assert!(!bson_ok(&[0, 0, 0, 0, 0x10, b'o', b'k']));
+    }
+}
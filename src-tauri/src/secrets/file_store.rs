@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+use crate::error::LdbError;
+use crate::state::{atomic_write, StateLock, StateManager};
+
+/// AES-GCM nonce length in bytes
+const NONCE_LEN: usize = 12;
+
+/// Encrypted secret entries, keyed by secret key
+type Entries = BTreeMap<String, String>;
+
+struct Paths {
+    /// Random 256-bit key, readable only by the current user
+    key: PathBuf,
+    /// JSON map of key -> base64(nonce || ciphertext)
+    entries: PathBuf,
+    lock: PathBuf,
+}
+
+fn paths() -> Result<Paths, LdbError> {
+    let data_dir = StateManager::get_data_dir()?;
+
+    Ok(Paths {
+        key: data_dir.join("secrets.key"),
+        entries: data_dir.join("secrets.enc.json"),
+        lock: data_dir.join("secrets.lock"),
+    })
+}
+
+/// Encrypt and store a secret
+pub fn store(key: &str, secret: &str) -> Result<(), LdbError> {
+    let paths = paths()?;
+    let lock = StateLock::acquire(&paths.lock)?;
+
+    create_key(&paths, &lock)?;
+    let cipher = cipher(&paths)?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    // Bind the ciphertext to its key so entries cannot be swapped
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: secret.as_bytes(), aad: key.as_bytes() })
+        .map_err(|e| LdbError::secret("Failed to encrypt secret", e))?;
+
+    let mut blob = nonce.to_vec();
+    blob.extend_from_slice(&ciphertext);
+
+    let mut entries = read_entries(&paths)?;
+    entries.insert(key.to_string(), BASE64.encode(blob));
+    write_entries(&paths, &entries)
+}
+
+/// Decrypt a stored secret
+pub fn load(key: &str) -> Result<String, LdbError> {
+    let paths = paths()?;
+    let entries = read_entries(&paths)?;
+
+    let encoded = entries
+        .get(key)
+        .ok_or_else(|| LdbError::Secret { message: format!("Secret not found: {}", key) })?;
+    let blob = BASE64
+        .decode(encoded)
+        .map_err(|e| LdbError::secret("Failed to decode secret", e))?;
+
+    if blob.len() < NONCE_LEN {
+        return Err(LdbError::Secret { message: format!("Secret is truncated: {}", key) });
+    }
+    let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+
+    let plaintext = cipher(&paths)?
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: key.as_bytes() })
+        .map_err(|e| LdbError::secret("Failed to decrypt secret", e))?;
+
+    String::from_utf8(plaintext).map_err(|e| LdbError::secret("Secret is not valid UTF-8", e))
+}
+
+/// Remove a stored secret
+pub fn delete(key: &str) -> Result<(), LdbError> {
+    let paths = paths()?;
+    let _lock = StateLock::acquire(&paths.lock)?;
+
+    let mut entries = read_entries(&paths)?;
+    if entries.remove(key).is_some() {
+        write_entries(&paths, &entries)?;
+    }
+
+    Ok(())
+}
+
+/// Generate the encryption key on first use; callers hold the store lock
+///
+/// A missing key next to stored entries is an error: a new key could not
+/// decrypt them, and the next write would replace them.
+fn create_key(paths: &Paths, _lock: &StateLock) -> Result<(), LdbError> {
+    if paths.key.exists() {
+        return Ok(());
+    }
+
+    if !read_entries(paths)?.is_empty() {
+        return Err(missing_key());
+    }
+
+    let key = Aes256Gcm::generate_key(OsRng);
+    atomic_write(&paths.key, key.as_slice())
+}
+
+fn missing_key() -> LdbError {
+    LdbError::Secret {
+        message: "Secrets key file is missing, stored secrets cannot be decrypted".to_string(),
+    }
+}
+
+/// Load the encryption key
+fn cipher(paths: &Paths) -> Result<Aes256Gcm, LdbError> {
+    if !paths.key.exists() {
+        return Err(missing_key());
+    }
+
+    let bytes = fs::read(&paths.key)
+        .map_err(|e| LdbError::secret("Failed to read secrets key", e))?;
+
+    if bytes.len() != 32 {
+        return Err(LdbError::Secret { message: "Secrets key file is corrupt".to_string() });
+    }
+
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes)))
+}
+
+fn read_entries(paths: &Paths) -> Result<Entries, LdbError> {
+    if !paths.entries.exists() {
+        return Ok(Entries::new());
+    }
+
+    let content = fs::read_to_string(&paths.entries)
+        .map_err(|e| LdbError::secret("Failed to read secrets file", e))?;
+
+    serde_json::from_str(&content).map_err(|e| LdbError::secret("Failed to parse secrets file", e))
+}
+
+fn write_entries(paths: &Paths, entries: &Entries) -> Result<(), LdbError> {
+    let content = serde_json::to_vec_pretty(entries)
+        .map_err(|e| LdbError::secret("Failed to serialize secrets", e))?;
+
+    atomic_write(&paths.entries, &content)
+}
@@ -0,0 +1,81 @@
+/// Secrets module
+///
+/// Keeps instance passwords out of the state database. Secrets go to the OS
+/// secret service when one is reachable and to an encrypted file otherwise;
+/// the returned `SecretRef` records which backend holds them.
+mod file_store;
+
+use keyring::Entry;
+
+use crate::error::LdbError;
+use crate::models::secret::{SecretBackend, SecretRef};
+
+/// Service name secrets are registered under in the OS secret service
+const KEYRING_SERVICE: &str = "ldb-engine";
+
+/// Secret key for an instance's root password
+pub fn instance_password_key(instance_id: &str) -> String {
+    format!("instance/{}/root-password", instance_id)
+}
+
+/// Store a secret under `key`, preferring the OS secret service
+pub fn store(key: &str, secret: &str) -> Result<SecretRef, LdbError> {
+    match keyring_store(key, secret) {
+        Ok(()) => Ok(SecretRef {
+            backend: SecretBackend::Keyring,
+            key: key.to_string(),
+        }),
+        Err(e) => {
+            eprintln!("OS secret service unavailable ({}), using encrypted file store", e);
+            file_store::store(key, secret)?;
+
+            Ok(SecretRef {
+                backend: SecretBackend::EncryptedFile,
+                key: key.to_string(),
+            })
+        }
+    }
+}
+
+/// Read the secret a reference points to
+pub fn load(secret_ref: &SecretRef) -> Result<String, LdbError> {
+    match secret_ref.backend {
+        SecretBackend::Keyring => keyring_entry(&secret_ref.key)?
+            .get_password()
+            .map_err(|e| LdbError::secret("Failed to read secret from keyring", e)),
+        SecretBackend::EncryptedFile => file_store::load(&secret_ref.key),
+    }
+}
+
+/// Delete the secret a reference points to; missing secrets are not an error
+pub fn delete(secret_ref: &SecretRef) -> Result<(), LdbError> {
+    match secret_ref.backend {
+        SecretBackend::Keyring => match keyring_entry(&secret_ref.key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(LdbError::secret("Failed to delete secret from keyring", e)),
+        },
+        SecretBackend::EncryptedFile => file_store::delete(&secret_ref.key),
+    }
+}
+
+/// Resolve an optional reference, treating "no secret" as an empty password
+pub fn resolve(secret_ref: Option<&SecretRef>) -> Result<String, LdbError> {
+    secret_ref.map(load).transpose().map(Option::unwrap_or_default)
+}
+
+fn keyring_entry(key: &str) -> Result<Entry, LdbError> {
+    Entry::new(KEYRING_SERVICE, key).map_err(|e| LdbError::secret("Invalid keyring entry", e))
+}
+
+/// Write to the keyring and read it back, so a backend that silently
+/// drops secrets (e.g. no unlocked collection) is treated as unavailable
+fn keyring_store(key: &str, secret: &str) -> Result<(), keyring::Error> {
+    let entry = Entry::new(KEYRING_SERVICE, key)?;
+    entry.set_password(secret)?;
+
+    if entry.get_password()? != secret {
+        return Err(keyring::Error::NoEntry);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+
+use crate::error::LdbError;
+use crate::models::instance::Instance;
+use crate::secrets;
+
+use super::{atomic_write, insert_instance};
+
+/// Meta key recording that the legacy instances.json has been imported
+const LEGACY_IMPORTED_KEY: &str = "legacy_json_imported";
+
+/// Instance as stored in instances.json, with its password in clear text
+#[derive(Deserialize)]
+struct LegacyInstance {
+    #[serde(flatten)]
+    instance: Instance,
+    #[serde(default)]
+    root_password: String,
+}
+
+/// Import instances from the pre-SQLite `instances.json` file, once.
+///
+/// Instances already present in the database are left untouched. Plaintext
+/// passwords are moved into the secret store, and after a successful import
+/// the file is replaced by an `instances.json.imported` backup without them.
+pub fn import_legacy_json(conn: &mut Connection, json_file: &Path) -> Result<usize, LdbError> {
+    let already_imported: Option<String> = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = ?1",
+            params![LEGACY_IMPORTED_KEY],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| LdbError::state("Failed to read import marker", e))?;
+
+    if already_imported.is_some() || !json_file.exists() {
+        return Ok(0);
+    }
+
+    let content = fs::read_to_string(json_file)
+        .map_err(|e| LdbError::state("Failed to read legacy instances file", e))?;
+
+    let legacy: Vec<LegacyInstance> = serde_json::from_str(&content)
+        .map_err(|e| LdbError::state("Failed to parse legacy instances file", e))?;
+
+    let tx = conn
+        .transaction()
+        .map_err(|e| LdbError::state("Failed to start import", e))?;
+
+    let mut instances = Vec::with_capacity(legacy.len());
+    let mut imported = 0;
+    for LegacyInstance { mut instance, root_password } in legacy {
+        let exists: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM instances WHERE id = ?1)",
+                params![instance.id.to_string()],
+                |row| row.get(0),
+            )
+            .map_err(|e| LdbError::state(&format!("Failed to check instance {}", instance.id), e))?;
+
+        if !exists {
+            if !root_password.is_empty() {
+                let key = secrets::instance_password_key(&instance.id.to_string());
+                instance.password_ref = Some(secrets::store(&key, &root_password)?);
+            }
+
+            insert_instance(&tx, &instance)?;
+            imported += 1;
+        }
+
+        instances.push(instance);
+    }
+
+    tx.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+        params![LEGACY_IMPORTED_KEY, chrono::Utc::now().to_rfc3339()],
+    )
+    .map_err(|e| LdbError::state("Failed to record import marker", e))?;
+
+    tx.commit()
+        .map_err(|e| LdbError::state("Failed to commit import", e))?;
+
+    // Keep the old file around as a backup, without passwords; the marker
+    // prevents re-import
+    let backup = json_file.with_extension("json.imported");
+    let scrubbed = serde_json::to_vec_pretty(&instances)
+        .map_err(|e| LdbError::state("Failed to serialize instances backup", e))?;
+    atomic_write(&backup, &scrubbed)?;
+    fs::remove_file(json_file)
+        .map_err(|e| LdbError::state("Failed to remove legacy instances file", e))?;
+
+    Ok(imported)
+}
@@ -0,0 +1,39 @@
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+use fs4::fs_std::FileExt;
+
+use crate::error::LdbError;
+
+/// Advisory lock held around every read-modify-write of the state store
+///
+/// SQLite serializes individual statements, but commands such as
+/// `create_instance` read, modify and write across several steps. The lock
+/// file makes those sequences exclusive between threads and processes.
+/// The lock is released when the guard is dropped.
+pub struct StateLock {
+    file: File,
+}
+
+impl StateLock {
+    /// Block until the exclusive lock on `path` is acquired
+    pub fn acquire(path: &Path) -> Result<Self, LdbError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .map_err(|e| LdbError::state("Failed to open state lock file", e))?;
+
+        FileExt::lock_exclusive(&file)
+            .map_err(|e| LdbError::state("Failed to lock state", e))?;
+
+        Ok(Self { file })
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
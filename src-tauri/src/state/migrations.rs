@@ -0,0 +1,197 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection, Transaction};
+
+use crate::error::LdbError;
+use crate::secrets;
+
+/// A single schema step: plain SQL, or Rust code for data migrations
+enum Migration {
+    Sql(&'static str),
+    Rust(fn(&Transaction) -> Result<(), LdbError>),
+    /// Rust code that cannot run in a transaction, such as VACUUM; must be idempotent
+    Maintenance(fn(&Connection) -> Result<(), LdbError>),
+}
+
+/// Ordered schema migrations for the state database.
+///
+/// Each entry upgrades the schema by one version; the current version is
+/// tracked in SQLite's `user_version` pragma. Never edit an entry that has
+/// already shipped - append a new one instead.
+const MIGRATIONS: &[Migration] = &[
+    // v1: instance metadata and a key/value table for app-level flags
+    Migration::Sql("CREATE TABLE instances (
+        id TEXT PRIMARY KEY NOT NULL,
+        name TEXT NOT NULL,
+        database_type TEXT NOT NULL,
+        image TEXT NOT NULL,
+        tag TEXT NOT NULL,
+        port INTEGER NOT NULL,
+        root_password TEXT NOT NULL,
+        status TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        volume_path TEXT
+    );
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY NOT NULL,
+        value TEXT NOT NULL
+    );"),
+    // v2: passwords move to the secret store, rows keep a reference
+    Migration::Sql("ALTER TABLE instances ADD COLUMN password_ref TEXT"),
+    // v3: move existing plaintext passwords into the secret store
+    Migration::Rust(move_plaintext_passwords),
+    // v4: drop the now-empty plaintext column
+    Migration::Sql("ALTER TABLE instances DROP COLUMN root_password"),
+    // v5: user preferences, such as the container engine to connect to
+    Migration::Sql("CREATE TABLE settings (
+        key TEXT PRIMARY KEY NOT NULL,
+        value TEXT NOT NULL
+    )"),
+    // v6: host address ports are published on; existing containers listen everywhere
+    Migration::Sql("ALTER TABLE instances ADD COLUMN bind_address TEXT NOT NULL DEFAULT 'all'"),
+    // v7: named ports beyond the primary one, as a JSON object
+    Migration::Sql("ALTER TABLE instances ADD COLUMN extra_ports TEXT"),
+    // v8: wipe the plaintext passwords v3/v4 left in freed pages and the WAL
+    Migration::Maintenance(scrub_freed_pages),
+    // v9: tell adopted containers' mounts apart from the app's own volume directories
+    Migration::Rust(mark_adopted_mounts),
+];
+
+/// Get the schema version the database is currently at
+pub fn current_version(conn: &Connection) -> Result<usize, LdbError> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get::<_, i64>(0))
+        .map(|v| v as usize)
+        .map_err(|e| super::sqlite_error("Failed to read schema version", e))
+}
+
+/// Apply every migration newer than the database's current version
+///
+/// Returns the number of migrations applied.
+pub fn run(conn: &mut Connection) -> Result<usize, LdbError> {
+    let version = current_version(conn)?;
+
+    // A downgrade; the database is fine, this app just cannot read it
+    if version > MIGRATIONS.len() {
+        return Err(LdbError::StateTooNew {
+            version,
+            supported: MIGRATIONS.len(),
+        });
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        if let Migration::Maintenance(apply) = migration {
+            apply(conn).map_err(|e| LdbError::state(&format!("Failed to apply migration v{}", index + 1), e))?;
+            conn.execute_batch(&format!("PRAGMA user_version = {}", index + 1))
+                .map_err(|e| LdbError::state(&format!("Failed to record migration v{}", index + 1), e))?;
+            continue;
+        }
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| LdbError::state("Failed to start migration", e))?;
+
+        match migration {
+            Migration::Sql(sql) => tx
+                .execute_batch(sql)
+                .map_err(|e| super::sqlite_error(&format!("Failed to apply migration v{}", index + 1), e))?,
+            Migration::Rust(apply) => apply(&tx)
+                .map_err(|e| LdbError::state(&format!("Failed to apply migration v{}", index + 1), e))?,
+            Migration::Maintenance(_) => unreachable!("maintenance steps run outside a transaction"),
+        }
+
+        // PRAGMA does not accept bound parameters
+        tx.execute_batch(&format!("PRAGMA user_version = {}", index + 1))
+            .map_err(|e| LdbError::state(&format!("Failed to record migration v{}", index + 1), e))?;
+
+        tx.commit()
+            .map_err(|e| LdbError::state(&format!("Failed to commit migration v{}", index + 1), e))?;
+    }
+
+    Ok(MIGRATIONS.len() - version)
+}
+
+/// Move every non-empty `root_password` into the secret store
+fn move_plaintext_passwords(tx: &Transaction) -> Result<(), LdbError> {
+    let plaintext: Vec<(String, String)> = {
+        let mut stmt = tx
+            .prepare("SELECT id, root_password FROM instances WHERE root_password != ''")
+            .map_err(|e| LdbError::state("Failed to query passwords", e))?;
+
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| LdbError::state("Failed to query passwords", e))?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| LdbError::state("Failed to read passwords", e))?
+    };
+
+    for (id, password) in plaintext {
+        let secret_ref = secrets::store(&secrets::instance_password_key(&id), &password)?;
+
+        tx.execute(
+            "UPDATE instances SET password_ref = ?2, root_password = '' WHERE id = ?1",
+            params![id, super::to_json(&secret_ref)?],
+        )
+        .map_err(|e| LdbError::state("Failed to update password reference", e))?;
+    }
+
+    Ok(())
+}
+
+/// Add `adopted_mount`, set for every volume path other than the app's own directory
+///
+/// Until now adopted bind mounts and named volumes were stored like the
+/// directories the app creates, at `<volumes>/<instance id>`.
+fn mark_adopted_mounts(tx: &Transaction) -> Result<(), LdbError> {
+    tx.execute_batch("ALTER TABLE instances ADD COLUMN adopted_mount INTEGER NOT NULL DEFAULT 0")
+        .map_err(|e| LdbError::state("Failed to add adopted_mount column", e))?;
+
+    let volume_paths: Vec<(String, String)> = {
+        let mut stmt = tx
+            .prepare("SELECT id, volume_path FROM instances WHERE volume_path IS NOT NULL")
+            .map_err(|e| LdbError::state("Failed to query volume paths", e))?;
+
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| LdbError::state("Failed to query volume paths", e))?;
+
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| LdbError::state("Failed to read volume paths", e))?
+    };
+
+    let volume_dir = super::StateManager::get_volume_dir()?;
+    for (id, volume_path) in volume_paths {
+        if Path::new(&volume_path) == volume_dir.join(&id) {
+            continue;
+        }
+
+        tx.execute("UPDATE instances SET adopted_mount = 1 WHERE id = ?1", params![id])
+            .map_err(|e| LdbError::state("Failed to mark adopted mount", e))?;
+    }
+
+    Ok(())
+}
+
+/// Rewrite the database file so no freed page keeps old row contents
+///
+/// Dropping a column or overwriting a value only marks the old bytes free.
+/// VACUUM rebuilds the file from live rows, with secure_delete zeroing
+/// anything freed meanwhile, and the checkpoint moves the result out of the
+/// WAL and truncates it, discarding older frames.
+fn scrub_freed_pages(conn: &Connection) -> Result<(), LdbError> {
+    conn.execute_batch("PRAGMA secure_delete = ON; VACUUM;")
+        .map_err(|e| LdbError::state("Failed to vacuum state database", e))?;
+
+    // Returns (busy, WAL frames, frames checkpointed); busy means a reader held it back
+    let busy: i64 = conn
+        .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| row.get(0))
+        .map_err(|e| LdbError::state("Failed to checkpoint state database", e))?;
+
+    if busy != 0 {
+        return Err(LdbError::State {
+            message: "State database is in use; could not truncate its write-ahead log".to_string(),
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,521 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rusqlite::{params, Connection, ErrorCode, OptionalExtension, Row, Transaction, TransactionBehavior};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::LdbError;
+use crate::models::instance::Instance;
+
+pub(crate) use lock::StateLock;
+pub(crate) use snapshot::atomic_write;
+
+mod import;
+mod lock;
+mod migrations;
+mod snapshot;
+
+/// How long a connection waits for another writer before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Columns selected when reading an instance row, in `instance_from_row` order
+const INSTANCE_COLUMNS: &str =
+    "id, name, database_type, image, tag, port, password_ref, status, created_at, volume_path, bind_address, extra_ports, adopted_mount";
+
+/// State manager for persisting instance metadata
+pub struct StateManager {
+    /// Path to the SQLite state database
+    db_file: PathBuf,
+    /// Advisory lock file guarding read-modify-write sequences
+    lock_file: PathBuf,
+    /// Last known good copy of the instances, used for recovery
+    snapshot_file: PathBuf,
+}
+
+impl StateManager {
+    /// Create a new StateManager with the default data directory
+    ///
+    /// Opens (or creates) `state.db`, applies pending schema migrations and
+    /// imports a legacy `instances.json` the first time it is seen. If the
+    /// database is corrupt it is rebuilt from the last good snapshot; any
+    /// other failure is returned as is.
+    pub fn new() -> Result<Self, LdbError> {
+        let data_dir = Self::get_data_dir()?;
+
+        // Ensure data directory exists
+        if !data_dir.exists() {
+            fs::create_dir_all(&data_dir)
+                .map_err(|e| LdbError::state("Failed to create data directory", e))?;
+        }
+
+        let manager = Self {
+            db_file: data_dir.join("state.db"),
+            lock_file: data_dir.join("state.lock"),
+            snapshot_file: data_dir.join("instances.snapshot.json"),
+        };
+
+        let _lock = StateLock::acquire(&manager.lock_file)?;
+
+        let initialized = manager.connect().and_then(|mut conn| {
+            let migrated = migrations::run(&mut conn)?;
+            let imported = import::import_legacy_json(&mut conn, &data_dir.join("instances.json"))?;
+            Ok(migrated + imported)
+        });
+
+        match initialized {
+            // Seed the snapshot so recovery is possible before the first write,
+            // and rewrite it after migrations so it matches the new schema
+            Ok(changed) if changed > 0 || !manager.snapshot_file.exists() => {
+                let instances = manager.connect().and_then(|conn| query_instances(&conn))?;
+                snapshot::write(&manager.snapshot_file, &instances)?;
+            }
+            Ok(_) => {}
+            Err(e @ LdbError::StateCorrupt { .. }) => {
+                manager.restore_from_snapshot(e)?;
+            }
+            Err(e) => return Err(e),
+        }
+
+        Ok(manager)
+    }
+
+    /// Get the data directory path (~/.ldb-engine/)
+    pub fn get_data_dir() -> Result<PathBuf, LdbError> {
+        let home = dirs::home_dir()
+            .ok_or_else(|| LdbError::State { message: "Could not find home directory".to_string() })?;
+
+        Ok(home.join(".ldb-engine"))
+    }
+
+    /// Get the volume directory path (~/.ldb-engine/volumes/)
+    pub fn get_volume_dir() -> Result<PathBuf, LdbError> {
+        let data_dir = Self::get_data_dir()?;
+        let volume_dir = data_dir.join("volumes");
+
+        if !volume_dir.exists() {
+            fs::create_dir_all(&volume_dir)
+                .map_err(|e| LdbError::state("Failed to create volume directory", e))?;
+        }
+
+        Ok(volume_dir)
+    }
+
+    /// Get the volume path for a specific instance
+    pub fn get_instance_volume_path(instance_id: &str) -> Result<PathBuf, LdbError> {
+        let volume_dir = Self::get_volume_dir()?;
+        let instance_volume_dir = volume_dir.join(instance_id);
+
+        if !instance_volume_dir.exists() {
+            fs::create_dir_all(&instance_volume_dir)
+                .map_err(|e| LdbError::state("Failed to create instance volume directory", e))?;
+        }
+
+        Ok(instance_volume_dir)
+    }
+
+    /// Open a connection to the state database
+    fn connect(&self) -> Result<Connection, LdbError> {
+        let conn = Connection::open(&self.db_file)
+            .map_err(|e| sqlite_error("Failed to open state database", e))?;
+
+        conn.busy_timeout(BUSY_TIMEOUT)
+            .map_err(|e| sqlite_error("Failed to configure state database", e))?;
+
+        // WAL + FULL sync: a crash mid-write rolls back to the last commit.
+        // This is the first statement to read the file, so a damaged header shows up here
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+            .map_err(|e| sqlite_error("Failed to configure state database", e))?;
+        conn.pragma_update(None, "synchronous", "FULL")
+            .map_err(|e| sqlite_error("Failed to configure state database", e))?;
+
+        Ok(conn)
+    }
+
+    /// Run a write under the state lock in a single immediate transaction
+    ///
+    /// The snapshot is refreshed after every successful commit so recovery
+    /// always has the latest good copy.
+    fn write<T>(&self, f: impl FnOnce(&Transaction) -> Result<T, LdbError>) -> Result<T, LdbError> {
+        let _lock = StateLock::acquire(&self.lock_file)?;
+        let mut conn = self.connect()?;
+
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| LdbError::state("Failed to start transaction", e))?;
+
+        let result = f(&tx)?;
+
+        tx.commit()
+            .map_err(|e| LdbError::state("Failed to commit state", e))?;
+
+        match query_instances(&conn) {
+            Ok(instances) => {
+                if let Err(e) = snapshot::write(&self.snapshot_file, &instances) {
+                    eprintln!("Failed to write state snapshot: {}", e);
+                }
+            }
+            Err(e) => eprintln!("Failed to read state for snapshot: {}", e),
+        }
+
+        Ok(result)
+    }
+
+    /// Rebuild the database from the last good snapshot
+    ///
+    /// The unreadable database is moved aside rather than deleted so it can
+    /// still be inspected. Fails with `cause` if no snapshot exists.
+    /// Callers must hold the state lock.
+    fn restore_from_snapshot(&self, cause: LdbError) -> Result<Vec<Instance>, LdbError> {
+        let instances = match snapshot::read(&self.snapshot_file) {
+            Ok(Some(instances)) => instances,
+            Ok(None) => return Err(cause),
+            Err(e) => {
+                return Err(LdbError::StateCorrupt {
+                    message: format!("{} (snapshot unusable: {})", cause, e),
+                })
+            }
+        };
+
+        eprintln!("State database unreadable ({}), restoring last good snapshot", cause);
+
+        let suffix = format!("corrupt-{}", chrono::Utc::now().format("%Y%m%d%H%M%S"));
+        for ext in ["", "-wal", "-shm"] {
+            let path = PathBuf::from(format!("{}{}", self.db_file.display(), ext));
+            if path.exists() {
+                let aside = PathBuf::from(format!("{}.{}", path.display(), suffix));
+                fs::rename(&path, &aside)
+                    .map_err(|e| LdbError::state("Failed to move corrupt state database aside", e))?;
+            }
+        }
+
+        let mut conn = self.connect()?;
+        migrations::run(&mut conn)?;
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| LdbError::state("Failed to start transaction", e))?;
+        for instance in &instances {
+            insert_instance(&tx, instance)?;
+        }
+        tx.commit()
+            .map_err(|e| LdbError::state("Failed to restore state", e))?;
+
+        Ok(instances)
+    }
+
+    /// Load all instances from the database, oldest first
+    ///
+    /// Falls back to the last good snapshot if the database is corrupt.
+    pub fn load_instances(&self) -> Result<Vec<Instance>, LdbError> {
+        match self.connect().and_then(|conn| query_instances(&conn)) {
+            Err(e @ LdbError::StateCorrupt { .. }) => {
+                let _lock = StateLock::acquire(&self.lock_file)?;
+                self.restore_from_snapshot(e)
+            }
+            result => result,
+        }
+    }
+
+    /// Replace all stored instances with the given list
+    pub fn save_instances(&self, instances: &[Instance]) -> Result<(), LdbError> {
+        self.write(|tx| {
+            tx.execute("DELETE FROM instances", [])
+                .map_err(|e| LdbError::state("Failed to clear instances", e))?;
+
+            for instance in instances {
+                insert_instance(tx, instance)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Add a new instance
+    pub fn add_instance(&self, instance: Instance) -> Result<(), LdbError> {
+        self.write(|tx| insert_instance(tx, &instance))
+    }
+
+    /// Update an existing instance
+    pub fn update_instance(&self, instance: Instance) -> Result<(), LdbError> {
+        self.write(|tx| update_instance_row(tx, &instance))
+    }
+
+    /// Atomically read, modify and write back a single instance
+    ///
+    /// Returns the updated instance.
+    pub fn modify_instance(
+        &self,
+        id: &str,
+        f: impl FnOnce(&mut Instance),
+    ) -> Result<Instance, LdbError> {
+        self.write(|tx| {
+            let mut instance = query_instance(tx, id)?
+                .ok_or_else(|| LdbError::InstanceNotFound { id: id.to_string() })?;
+
+            f(&mut instance);
+            update_instance_row(tx, &instance)?;
+
+            Ok(instance)
+        })
+    }
+
+    /// Remove an instance by ID
+    pub fn remove_instance(&self, id: &str) -> Result<(), LdbError> {
+        self.write(|tx| {
+            let removed = tx
+                .execute("DELETE FROM instances WHERE id = ?1", params![id])
+                .map_err(|e| LdbError::state("Failed to remove instance", e))?;
+
+            if removed == 0 {
+                return Err(LdbError::InstanceNotFound { id: id.to_string() });
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Get an instance by ID
+    pub fn get_instance(&self, id: &str) -> Result<Option<Instance>, LdbError> {
+        match self.connect().and_then(|conn| query_instance(&conn, id)) {
+            Err(LdbError::StateCorrupt { .. }) => Ok(self
+                .load_instances()?
+                .into_iter()
+                .find(|i| i.id.to_string() == id)),
+            result => result,
+        }
+    }
+
+    /// Get a user setting, if set
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>, LdbError> {
+        self.connect()?
+            .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()
+            .map_err(|e| LdbError::state("Failed to read setting", e))
+    }
+
+    /// Set a user setting; `None` resets it to the default
+    pub fn set_setting(&self, key: &str, value: Option<&str>) -> Result<(), LdbError> {
+        self.write(|tx| {
+            match value {
+                Some(value) => tx.execute(
+                    "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                    params![key, value],
+                ),
+                None => tx.execute("DELETE FROM settings WHERE key = ?1", params![key]),
+            }
+            .map_err(|e| LdbError::state("Failed to save setting", e))?;
+
+            Ok(())
+        })
+    }
+
+    /// Get the path to the state database
+    pub fn get_database_path(&self) -> &PathBuf {
+        &self.db_file
+    }
+}
+
+impl Default for StateManager {
+    fn default() -> Self {
+        Self::new().expect("Failed to create StateManager")
+    }
+}
+
+/// Read all instance rows, oldest first
+fn query_instances(conn: &Connection) -> Result<Vec<Instance>, LdbError> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM instances ORDER BY created_at",
+            INSTANCE_COLUMNS
+        ))
+        .map_err(|e| sqlite_error("Failed to query instances", e))?;
+
+    let rows = stmt
+        .query_map([], instance_from_row)
+        .map_err(|e| sqlite_error("Failed to query instances", e))?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| sqlite_error("Failed to read instances", e))
+}
+
+/// Read a single instance row by ID
+fn query_instance(conn: &Connection, id: &str) -> Result<Option<Instance>, LdbError> {
+    conn.query_row(
+        &format!("SELECT {} FROM instances WHERE id = ?1", INSTANCE_COLUMNS),
+        params![id],
+        instance_from_row,
+    )
+    .optional()
+    .map_err(|e| sqlite_error("Failed to read instance", e))
+}
+
+/// Map a SQLite error, telling corruption apart from every other failure
+///
+/// Only a damaged file (SQLITE_CORRUPT, SQLITE_NOTADB) or a row that no
+/// longer decodes is `StateCorrupt`, the one error that restores from the
+/// snapshot; busy, I/O and permission errors leave the database alone.
+fn sqlite_error(context: &str, error: rusqlite::Error) -> LdbError {
+    let damaged = matches!(
+        error.sqlite_error_code(),
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+    );
+    let undecodable = matches!(
+        error,
+        rusqlite::Error::FromSqlConversionFailure(..)
+            | rusqlite::Error::InvalidColumnType(..)
+            | rusqlite::Error::IntegralValueOutOfRange(..)
+    );
+
+    if damaged || undecodable {
+        LdbError::state_corrupt(context, error)
+    } else {
+        LdbError::state(context, error)
+    }
+}
+
+/// Overwrite an existing instance row
+fn update_instance_row(conn: &Connection, instance: &Instance) -> Result<(), LdbError> {
+    let updated = conn
+        .execute(
+            "UPDATE instances SET name = ?2, database_type = ?3, image = ?4, tag = ?5,
+                port = ?6, password_ref = ?7, status = ?8, created_at = ?9, volume_path = ?10,
+                bind_address = ?11, extra_ports = ?12, adopted_mount = ?13
+             WHERE id = ?1",
+            params![
+                instance.id.to_string(),
+                instance.name,
+                to_text(&instance.database_type)?,
+                instance.image,
+                instance.tag,
+                instance.port,
+                instance.password_ref.as_ref().map(to_json).transpose()?,
+                to_text(&instance.status)?,
+                instance.created_at.to_rfc3339(),
+                instance.volume_path,
+                to_text(&instance.bind_address)?,
+                to_json(&instance.extra_ports)?,
+                instance.adopted_mount,
+            ],
+        )
+        .map_err(|e| LdbError::state("Failed to update instance", e))?;
+
+    if updated == 0 {
+        return Err(LdbError::InstanceNotFound { id: instance.id.to_string() });
+    }
+
+    Ok(())
+}
+
+/// Insert a single instance row
+fn insert_instance(conn: &Connection, instance: &Instance) -> Result<(), LdbError> {
+    conn.execute(
+        &format!(
+            "INSERT INTO instances ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            INSTANCE_COLUMNS
+        ),
+        params![
+            instance.id.to_string(),
+            instance.name,
+            to_text(&instance.database_type)?,
+            instance.image,
+            instance.tag,
+            instance.port,
+            instance.password_ref.as_ref().map(to_json).transpose()?,
+            to_text(&instance.status)?,
+            instance.created_at.to_rfc3339(),
+            instance.volume_path,
+            to_text(&instance.bind_address)?,
+            to_json(&instance.extra_ports)?,
+            instance.adopted_mount,
+        ],
+    )
+    .map_err(|e| LdbError::state(&format!("Failed to insert instance {}", instance.id), e))?;
+
+    Ok(())
+}
+
+/// Build an Instance from a row selected with `INSTANCE_COLUMNS`
+fn instance_from_row(row: &Row) -> rusqlite::Result<Instance> {
+    let id: String = row.get(0)?;
+    let created_at: String = row.get(8)?;
+
+    Ok(Instance {
+        id: uuid::Uuid::parse_str(&id).map_err(|e| conversion_error(0, e))?,
+        name: row.get(1)?,
+        database_type: from_text(&row.get::<_, String>(2)?).map_err(|e| conversion_error(2, e))?,
+        image: row.get(3)?,
+        tag: row.get(4)?,
+        port: row.get(5)?,
+        password_ref: row
+            .get::<_, Option<String>>(6)?
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| conversion_error(6, e))?,
+        status: from_text(&row.get::<_, String>(7)?).map_err(|e| conversion_error(7, e))?,
+        created_at: chrono::DateTime::parse_from_rfc3339(&created_at)
+            .map_err(|e| conversion_error(8, e))?
+            .with_timezone(&chrono::Utc),
+        volume_path: row.get(9)?,
+        bind_address: from_text(&row.get::<_, String>(10)?).map_err(|e| conversion_error(10, e))?,
+        extra_ports: row
+            .get::<_, Option<String>>(11)?
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(|e| conversion_error(11, e))?
+            .unwrap_or_default(),
+        adopted_mount: row.get(12)?,
+        container_name: None,
+    })
+}
+
+/// Store a unit enum as its serde name (e.g. `DatabaseType::PostgreSQL` -> "postgresql")
+fn to_text<T: Serialize>(value: &T) -> Result<String, LdbError> {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => Ok(s),
+        Ok(other) => Err(LdbError::State { message: format!("Expected a string value, got {}", other) }),
+        Err(e) => Err(LdbError::state("Failed to serialize value", e)),
+    }
+}
+
+/// Store a structured value as a JSON column
+fn to_json<T: Serialize>(value: &T) -> Result<String, LdbError> {
+    serde_json::to_string(value).map_err(|e| LdbError::state("Failed to serialize value", e))
+}
+
+/// Parse a unit enum back from its serde name
+fn from_text<T: DeserializeOwned>(text: &str) -> Result<T, serde_json::Error> {
+    serde_json::from_value(serde_json::Value::String(text.to_string()))
+}
+
+fn conversion_error<E: std::error::Error + Send + Sync + 'static>(
+    column: usize,
+    error: E,
+) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(column, rusqlite::types::Type::Text, Box::new(error))
+}
+
+/// Convenience function to load all instances
+pub fn load_instances() -> Result<Vec<Instance>, LdbError> {
+    let manager = StateManager::new()?;
+    manager.load_instances()
+}
+
+/// Convenience function to save all instances
+pub fn save_instances(instances: &[Instance]) -> Result<(), LdbError> {
+    let manager = StateManager::new()?;
+    manager.save_instances(instances)
+}
+
+/// Remove the volume directory for an instance
+pub fn remove_volume_dir(instance_id: &str) -> Result<(), LdbError> {
+    let volume_dir = StateManager::get_instance_volume_path(instance_id)?;
+
+    if volume_dir.exists() {
+        fs::remove_dir_all(&volume_dir)
+            .map_err(|e| LdbError::state("Failed to remove volume directory", e))?;
+    }
+
+    Ok(())
+}
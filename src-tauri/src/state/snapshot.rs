@@ -0,0 +1,77 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+
+use crate::error::LdbError;
+use crate::models::instance::Instance;
+
+/// Write `content` to `path` atomically
+///
+/// The data goes to a temporary file in the same directory, is flushed to
+/// disk, and then renamed over the target, so a crash leaves either the old
+/// or the new file - never a truncated one. The file is readable by its
+/// owner only from the moment it is created, as it may hold secrets.
+pub fn atomic_write(path: &Path, content: &[u8]) -> Result<(), LdbError> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| LdbError::State { message: format!("Invalid file path: {}", path.display()) })?;
+    let tmp_path = path.with_file_name(format!(".{}.tmp", file_name.to_string_lossy()));
+
+    // A leftover from a crash would keep its old permissions
+    match fs::remove_file(&tmp_path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            return Err(LdbError::state("Failed to remove stale temporary file", e))
+        }
+        _ => {}
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(&tmp_path)
+        .map_err(|e| LdbError::state("Failed to create temporary file", e))?;
+    file.write_all(content)
+        .map_err(|e| LdbError::state("Failed to write temporary file", e))?;
+    file.sync_all()
+        .map_err(|e| LdbError::state("Failed to flush temporary file", e))?;
+
+    fs::rename(&tmp_path, path)
+        .map_err(|e| LdbError::state(&format!("Failed to replace {}", path.display()), e))?;
+
+    // Persist the rename itself; not supported on every platform
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    Ok(())
+}
+
+/// Save the last known good set of instances
+pub fn write(path: &Path, instances: &[Instance]) -> Result<(), LdbError> {
+    let content = serde_json::to_vec_pretty(instances)
+        .map_err(|e| LdbError::state("Failed to serialize instances", e))?;
+
+    atomic_write(path, &content)
+}
+
+/// Read the last known good set of instances, if a snapshot exists
+pub fn read(path: &Path) -> Result<Option<Vec<Instance>>, LdbError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(path)
+        .map_err(|e| LdbError::state("Failed to read state snapshot", e))?;
+
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| LdbError::state_corrupt("Failed to parse state snapshot", e))
+}
@@ -1,6 +1,37 @@
-export type InstanceStatus = 'running' | 'stopped' | 'error' | 'creating';
+export type InstanceStatus = 'running' | 'stopped' | 'error' | 'creating' | 'starting' | 'ready' | 'unhealthy';
 
-export type DatabaseType = 'postgres' | 'redis' | 'mysql' | 'mongo';
+// Whether the container is up, whatever the database's readiness
+export function isRunning(status: InstanceStatus | string): boolean {
+  return status === 'running' || status === 'starting' || status === 'ready' || status === 'unhealthy';
+}
+
+// Payload of the instance-status-changed event
+export interface InstanceStatusEvent {
+  instance_id: string;
+  status: InstanceStatus;
+  message?: string;
+}
+
+export interface InstancePortChangedEvent {
+  instance_id: string;
+  port: number;
+  // Every host port by name, the main one under 'primary'
+  ports: PortMap;
+  connection_string: string;
+}
+
+// Host ports by name, e.g. { primary: 8123, native: 9000 }
+export type PortMap = Record<string, number>;
+
+export type DatabaseType = 'postgres' | 'redis' | 'valkey' | 'keydb' | 'mysql' | 'mariadb' | 'mongo' | 'clickhouse' | 'elasticsearch' | 'opensearch' | 'sqlserver';
+
+// 'loopback', 'loopback_dual_stack', 'all', or an interface IP address
+export type BindAddress = 'loopback' | 'loopback_dual_stack' | 'all' | (string & {});
+
+export interface SecretRef {
+  backend: 'keyring' | 'encrypted_file';
+  key: string;
+}
 
 export interface Instance {
   id: string;
@@ -9,10 +40,16 @@ export interface Instance {
   image: string;
   tag: string;
   port: number;
-  root_password: string;
+  bind_address: BindAddress;
+  // Host ports of additional named endpoints, e.g. ClickHouse's "native"
+  extra_ports?: PortMap;
+  password_ref?: SecretRef;
   status: InstanceStatus;
   created_at: string;
   volume_path?: string;
+  // volume_path is an adopted container's own mount; never deleted with the instance
+  adopted_mount?: boolean;
+  container_name?: string;
 }
 
 export interface CreateInstanceRequest {
@@ -22,6 +59,9 @@ export interface CreateInstanceRequest {
   tag: string;
   password: string;
   port?: number;
+  // Host ports wanted for named endpoints; the rest are picked automatically
+  ports?: PortMap;
+  bind_address?: BindAddress;
 }
 
 export interface ImageTag {
@@ -38,6 +78,59 @@ export interface SupportedImage {
   default_port: number;
 }
 
+export type EngineKind = 'docker' | 'podman';
+
+export interface EngineInfo {
+  kind: EngineKind;
+  rootless: boolean;
+  selinux: boolean;
+}
+
+// An engine address found by list_container_engines
+export interface EngineEndpoint {
+  name: string;
+  host: string;
+}
+
+export interface DockerStatus {
+  available: boolean;
+  host?: string;
+  engine?: EngineInfo;
+  version?: string;
+  api_version?: string;
+  os?: string;
+  arch?: string;
+  error?: string;
+}
+
+export interface PortRange {
+  start: number;
+  end: number;
+}
+
+export interface UnknownContainer {
+  container_id: string;
+  container_name: string;
+  image: string;
+  state: string;
+  instance_id?: string | null;
+}
+
+export interface OrphanedVolume {
+  instance_id: string;
+  path: string;
+}
+
+export interface ReconciliationReport {
+  missing_containers: Instance[];
+  unknown_containers: UnknownContainer[];
+  orphaned_volumes: OrphanedVolume[];
+}
+
+export type DiscrepancyKind = 'missing_container' | 'unknown_container' | 'orphaned_volume';
+
+export type ReconcileAction = 'recreate' | 'adopt' | 'purge';
+
 export interface PullProgress {
   id: string;
   status: string;
@@ -48,6 +141,52 @@ export interface PullProgress {
   };
 }
 
+// Error returned by every backend command (see src-tauri/src/error.rs)
+export type LdbErrorCode =
+  | 'DOCKER_UNAVAILABLE'
+  | 'DOCKER_ERROR'
+  | 'PORT_IN_USE'
+  | 'PORT_CONFLICT'
+  | 'NO_PORT_AVAILABLE'
+  | 'IMAGE_MISSING'
+  | 'INSTANCE_NOT_FOUND'
+  | 'NOT_READY'
+  | 'CONTAINER_NOT_FOUND'
+  | 'DATA_NOT_PERSISTED'
+  | 'STATE_CORRUPT'
+  | 'STATE_TOO_NEW'
+  | 'STATE_ERROR'
+  | 'SECRET_ERROR'
+  | 'REGISTRY_ERROR'
+  | 'INVALID_INPUT'
+  | 'INTERNAL_ERROR';
+
+export interface LdbError {
+  code: LdbErrorCode;
+  message: string;
+  // Structured fields, present depending on the code
+  port?: number;
+  suggested_port?: number;
+  start?: number;
+  image?: string;
+  id?: string;
+  data_path?: string;
+  expected?: string;
+  timeout_secs?: number;
+  version?: number;
+  supported?: number;
+}
+
+export function isLdbError(e: unknown): e is LdbError {
+  return typeof e === 'object' && e !== null && 'code' in e && 'message' in e;
+}
+
+export function errorMessage(e: unknown): string {
+  if (isLdbError(e)) return e.message;
+  if (e instanceof Error) return e.message;
+  return String(e);
+}
+
 export const SUPPORTED_IMAGES: SupportedImage[] = [
   { 
     id: 'postgres', 
@@ -63,6 +202,20 @@ export const SUPPORTED_IMAGES: SupportedImage[] = [
     description: 'In-memory data structure store',
     default_port: 6379
   },
+  { 
+    id: 'valkey', 
+    name: 'Valkey', 
+    hubName: 'valkey/valkey',
+    description: 'Open source Redis fork',
+    default_port: 6379
+  },
+  { 
+    id: 'keydb', 
+    name: 'KeyDB', 
+    hubName: 'eqalpha/keydb',
+    description: 'Multithreaded Redis-compatible store',
+    default_port: 6379
+  },
   { 
     id: 'mysql', 
     name: 'MySQL', 
@@ -70,6 +223,13 @@ export const SUPPORTED_IMAGES: SupportedImage[] = [
     description: 'Popular relational database',
     default_port: 3306
   },
+  { 
+    id: 'mariadb', 
+    name: 'MariaDB', 
+    hubName: 'library/mariadb',
+    description: 'Community-developed MySQL fork',
+    default_port: 3306
+  },
   { 
     id: 'mongo', 
     name: 'MongoDB', 
@@ -77,4 +237,32 @@ export const SUPPORTED_IMAGES: SupportedImage[] = [
     description: 'NoSQL document database',
     default_port: 27017
   },
+  { 
+    id: 'clickhouse', 
+    name: 'ClickHouse', 
+    hubName: 'clickhouse/clickhouse-server',
+    description: 'Column-oriented analytics database',
+    default_port: 8123
+  },
+  { 
+    id: 'elasticsearch', 
+    name: 'Elasticsearch', 
+    hubName: 'library/elasticsearch',
+    description: 'Distributed search and analytics engine',
+    default_port: 9200
+  },
+  { 
+    id: 'opensearch', 
+    name: 'OpenSearch', 
+    hubName: 'opensearchproject/opensearch',
+    description: 'Open source Elasticsearch fork',
+    default_port: 9200
+  },
+  { 
+    id: 'sqlserver', 
+    name: 'SQL Server', 
+    hubName: 'mcr.microsoft.com/mssql/server',
+    description: 'Microsoft relational database (Linux container)',
+    default_port: 1433
+  },
 ];